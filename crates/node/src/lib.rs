@@ -1,3 +1,16 @@
+use napi::bindgen_prelude::{AsyncTask, Buffer, Either, Uint32Array, Uint8Array};
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, Error, JsFunction, JsObject, JsUnknown, Result, Status, Task, ValueType};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use tailwindcss_oxide::candidate::{
+  normalize, Candidate, Important, Modifier, Utility, Value, Variant,
+};
+use tailwindcss_oxide::diagnostics::DiagnosticKind;
 use utf16::IndexConverter;
 
 #[macro_use]
@@ -5,14 +18,16 @@ extern crate napi_derive;
 
 mod utf16;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 #[napi(object)]
 pub struct ChangedContent {
   /// File path to the changed file
   pub file: Option<String>,
 
-  /// Contents of the changed file
-  pub content: Option<String>,
+  /// Contents of the changed file. A `Buffer` or `Uint8Array` is borrowed for the duration of the
+  /// call instead of being copied. Asynchronous scans outlive the call, so they copy it instead,
+  /// and reject content that isn't valid UTF-8.
+  pub content: Option<Either<String, Uint8Array>>,
 
   /// File extension
   pub extension: String,
@@ -35,12 +50,25 @@ pub struct GlobEntry {
   pub pattern: String,
 }
 
-impl From<ChangedContent> for tailwindcss_oxide::ChangedContent {
-  fn from(changed_content: ChangedContent) -> Self {
-    Self {
-      file: changed_content.file.map(Into::into),
-      content: changed_content.content,
-    }
+impl TryFrom<ChangedContent> for tailwindcss_oxide::ChangedContent {
+  type Error = Error;
+
+  fn try_from(changed_content: ChangedContent) -> Result<Self> {
+    let content = match changed_content.content {
+      Some(Either::A(content)) => Some(content),
+      // Lossy decoding would shift the byte offsets of everything after invalid sequences
+      Some(Either::B(content)) => Some(String::from_utf8(content.to_vec()).map_err(|e| {
+        Error::new(
+          Status::InvalidArg,
+          format!("Content is not valid UTF-8: {}", e),
+        )
+      })?),
+      None => None,
+    };
+
+    let mut changed = Self::new(changed_content.file.map(Into::into), content);
+    changed.extension = Some(changed_content.extension).filter(|x| !x.is_empty());
+    Ok(changed)
   }
 }
 
@@ -68,6 +96,328 @@ impl From<DetectSources> for tailwindcss_oxide::scanner::detect_sources::DetectS
   }
 }
 
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct SkipHeuristics {
+  /// Skip `data:` URIs outside of arbitrary values
+  pub data_uris: Option<bool>,
+
+  /// Skip runs of base64 characters of at least this many bytes. Use `0` to disable.
+  pub min_base64_length: Option<u32>,
+
+  /// Skip lines longer than this many bytes without whitespace. Use `0` to disable.
+  pub max_line_length: Option<u32>,
+}
+
+impl From<SkipHeuristics> for tailwindcss_oxide::scanner::heuristics::SkipHeuristics {
+  fn from(heuristics: SkipHeuristics) -> Self {
+    let defaults = Self::default();
+    let threshold = |value: Option<u32>, default: Option<usize>| match value {
+      Some(0) => None,
+      Some(value) => Some(value as usize),
+      None => default,
+    };
+
+    Self {
+      data_uris: heuristics.data_uris.unwrap_or(defaults.data_uris),
+      min_base64_length: threshold(heuristics.min_base64_length, defaults.min_base64_length),
+      max_line_length: threshold(heuristics.max_line_length, defaults.max_line_length),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct StrictExtraction {
+  /// Extensions of the files that are extracted strictly, defaults to markup languages, e.g.:
+  /// `html` or `tsx`
+  pub extensions: Option<Vec<String>>,
+
+  /// Functions whose string arguments contain classes, defaults to `clsx`, `cn`, `cva` and `tw`
+  pub helpers: Option<Vec<String>>,
+}
+
+impl From<StrictExtraction> for tailwindcss_oxide::StrictExtraction {
+  fn from(strict: StrictExtraction) -> Self {
+    let defaults = Self::default();
+
+    Self {
+      extensions: strict.extensions.unwrap_or(defaults.extensions),
+      helpers: strict.helpers.unwrap_or(defaults.helpers),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CustomExtractor {
+  /// Extensions of the files this extractor applies to, e.g.: `dsl`
+  pub extensions: Option<Vec<String>>,
+
+  /// Globs of the files this extractor applies to, matched against the full path
+  pub globs: Option<Vec<String>>,
+
+  /// Regexes whose capture groups contain the candidates, e.g.: `@classes\(([^)]*)\)`
+  pub patterns: Vec<String>,
+
+  /// Take the words of the matches as candidates as-is, instead of extracting from them
+  pub verbatim: Option<bool>,
+}
+
+impl TryFrom<CustomExtractor> for tailwindcss_oxide::scanner::custom_extractors::CustomExtractor {
+  type Error = Error;
+
+  fn try_from(extractor: CustomExtractor) -> Result<Self> {
+    let patterns = extractor
+      .patterns
+      .iter()
+      .map(|pattern| {
+        regex::bytes::Regex::new(pattern).map_err(|e| {
+          Error::new(
+            Status::InvalidArg,
+            format!("Invalid pattern `{}`: {}", pattern, e),
+          )
+        })
+      })
+      .collect::<Result<_>>()?;
+
+    Ok(Self {
+      extensions: extractor.extensions.unwrap_or_default(),
+      globs: extractor.globs.unwrap_or_default(),
+      patterns,
+      verbatim: extractor.verbatim.unwrap_or(false),
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ScanStats {
+  /// Amount of files (or in-memory contents) that were scanned
+  pub files_scanned: i64,
+
+  /// Amount of bytes that were read
+  pub bytes_scanned: i64,
+
+  /// Amount of bytes that were skipped by the skip heuristics
+  pub bytes_skipped: i64,
+}
+
+impl From<tailwindcss_oxide::ScanStats> for ScanStats {
+  fn from(stats: tailwindcss_oxide::ScanStats) -> Self {
+    Self {
+      files_scanned: stats.files_scanned as i64,
+      bytes_scanned: stats.bytes_scanned as i64,
+      bytes_skipped: stats.bytes_skipped as i64,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct DesignSystem {
+  /// Roots of functional utilities, e.g.: `bg`, `text`, `translate-x`
+  pub utilities: Vec<String>,
+
+  /// Static utilities, e.g.: `flex`, `underline`
+  pub static_utilities: Vec<String>,
+
+  /// Static, functional and compound variant names, e.g.: `hover`, `data`, `group`
+  pub variants: Vec<String>,
+
+  /// The prefix that every candidate has to start with, e.g.: `tw`
+  pub prefix: Option<String>,
+}
+
+impl From<DesignSystem> for tailwindcss_oxide::design_system::DesignSystem {
+  fn from(design_system: DesignSystem) -> Self {
+    Self {
+      utilities: design_system.utilities,
+      static_utilities: design_system.static_utilities,
+      variants: design_system.variants,
+      prefix: design_system.prefix,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateValue {
+  /// One of `named`, `arbitrary` or `var`
+  pub kind: String,
+
+  /// The value as written, e.g.: `red-500`, `#0088cc` or `--my-color`
+  pub value: String,
+
+  /// The type hint of arbitrary values and variables, e.g.: `length` in `text-[length:2px]`
+  pub data_type: Option<String>,
+
+  /// Named value followed by a named modifier, e.g.: `1/2` in `w-1/2`
+  pub fraction: Option<String>,
+}
+
+impl From<Value<'_>> for CandidateValue {
+  fn from(value: Value) -> Self {
+    let (kind, value, data_type, fraction) = match value {
+      Value::Named { value, fraction } => ("named", value, None, fraction),
+      Value::Arbitrary { data_type, value } => ("arbitrary", value, data_type, None),
+      Value::Var { data_type, name } => ("var", name, data_type, None),
+    };
+
+    Self {
+      kind: kind.into(),
+      value: value.into(),
+      data_type: data_type.map(Into::into),
+      fraction: fraction.map(Into::into),
+    }
+  }
+}
+
+impl From<Modifier<'_>> for CandidateValue {
+  fn from(modifier: Modifier) -> Self {
+    let (kind, value) = match modifier {
+      Modifier::Named(value) => ("named", value),
+      Modifier::Arbitrary(value) => ("arbitrary", value),
+      Modifier::Var(value) => ("var", value),
+    };
+
+    Self {
+      kind: kind.into(),
+      value: value.into(),
+      data_type: None,
+      fraction: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ParsedVariant {
+  /// One of `named` or `arbitrary`
+  pub kind: String,
+
+  /// The root of named variants, e.g.: `group` in `group-hover`
+  pub root: Option<String>,
+
+  /// The selector of arbitrary variants, e.g.: `&:hover` in `[&:hover]`
+  pub selector: Option<String>,
+
+  pub value: Option<CandidateValue>,
+
+  pub modifier: Option<CandidateValue>,
+}
+
+impl From<Variant<'_>> for ParsedVariant {
+  fn from(variant: Variant) -> Self {
+    match variant {
+      Variant::Arbitrary { selector } => Self {
+        kind: "arbitrary".into(),
+        root: None,
+        selector: Some(selector.into()),
+        value: None,
+        modifier: None,
+      },
+      Variant::Named {
+        root,
+        value,
+        modifier,
+      } => Self {
+        kind: "named".into(),
+        root: Some(root.into()),
+        selector: None,
+        value: value.map(Into::into),
+        modifier: modifier.map(Into::into),
+      },
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ParsedCandidate {
+  /// The candidate as it was written
+  pub raw: String,
+
+  /// The prefix, only known when parsed with a design system
+  pub prefix: Option<String>,
+
+  /// All variants in the order they were written
+  pub variants: Vec<ParsedVariant>,
+
+  /// One of `named` or `arbitrary` (for arbitrary properties, e.g.: `[color:red]`)
+  pub kind: String,
+
+  /// The root of named utilities, e.g.: `bg` in `bg-red-500`
+  pub root: Option<String>,
+
+  /// The property of arbitrary properties, e.g.: `color` in `[color:red]`
+  pub property: Option<String>,
+
+  pub value: Option<CandidateValue>,
+
+  pub modifier: Option<CandidateValue>,
+
+  /// One of `prefix` or `suffix`, when the candidate is marked as important
+  pub important: Option<String>,
+
+  pub negative: bool,
+}
+
+impl From<Candidate<'_>> for ParsedCandidate {
+  fn from(candidate: Candidate) -> Self {
+    let (kind, root, property, value, modifier) = match candidate.utility {
+      Utility::Named {
+        root,
+        value,
+        modifier,
+      } => ("named", Some(root), None, value.map(Into::into), modifier),
+      Utility::ArbitraryProperty {
+        property,
+        value,
+        modifier,
+      } => (
+        "arbitrary",
+        None,
+        Some(property),
+        Some(CandidateValue {
+          kind: "arbitrary".into(),
+          value: value.into(),
+          data_type: None,
+          fraction: None,
+        }),
+        modifier,
+      ),
+    };
+
+    Self {
+      raw: candidate.raw.into(),
+      prefix: candidate.prefix.map(Into::into),
+      variants: candidate.variants.into_iter().map(Into::into).collect(),
+      kind: kind.into(),
+      root: root.map(Into::into),
+      property: property.map(Into::into),
+      value,
+      modifier: modifier.map(Into::into),
+      important: candidate.important.map(|important| {
+        match important {
+          Important::Prefix => "prefix",
+          Important::Suffix => "suffix",
+        }
+        .into()
+      }),
+      negative: candidate.negative,
+    }
+  }
+}
+
+/// Parse a candidate into its structured form, returns `null` for invalid candidates. Roots are
+/// guessed from the syntax alone, use `Scanner#parseCandidate` to resolve them with the design
+/// system.
+#[napi]
+pub fn parse_candidate(candidate: String) -> Option<ParsedCandidate> {
+  Candidate::parse(&candidate).map(Into::into)
+}
+
 // ---
 
 #[derive(Debug, Clone)]
@@ -78,12 +428,151 @@ pub struct ScannerOptions {
 
   /// Glob sources
   pub sources: Option<Vec<GlobEntry>>,
+
+  /// Skip regions that can't contain candidates, e.g.: data URIs and minified lines
+  pub skip_heuristics: Option<SkipHeuristics>,
+
+  /// Amount of threads used for scanning, in a dedicated thread pool. Use `1` for sequential,
+  /// deterministic scanning, or `0` for the default amount of threads.
+  pub threads: Option<u32>,
+
+  /// Scan in a dedicated thread pool instead of the global one, even without `threads`
+  pub dedicated_thread_pool: Option<bool>,
+
+  /// Only return candidates known to the design system. All candidates are returned when not set.
+  pub design_system: Option<DesignSystem>,
+
+  /// Track equivalent spellings of candidates by one canonical candidate, e.g.: `!underline` and
+  /// `underline!` both belong to `underline!`. Candidates are still returned as they are spelled,
+  /// see `spellings` for the mapping.
+  pub normalize_candidates: Option<bool>,
+
+  /// Decode HTML entities in attribute values of HTML-like templates, e.g.: `&amp;` becomes `&`
+  pub decode_html_entities: Option<bool>,
+
+  /// Extensions of files where classes live inside of string literals, e.g.: `json`. Their escape
+  /// sequences are unescaped before extraction.
+  pub unescape_strings: Option<Vec<String>>,
+
+  /// Only extract the candidates of `@apply` rules from stylesheets that are passed in explicitly,
+  /// e.g.: `.css` files. The `<style>` blocks of components are always handled this way.
+  pub extract_stylesheets: Option<bool>,
+
+  /// Extensions of files whose comments are skipped, e.g.: `js`. In Markdown files, fenced code
+  /// blocks are skipped as well.
+  pub strip_comments: Option<Vec<String>>,
+
+  /// Only extract candidates from class-bearing contexts, e.g.: `class="…"` or `clsx(…)`
+  pub strict_extraction: Option<StrictExtraction>,
+
+  /// Regex based extractors for files that the generic extractor can't handle. The patterns are
+  /// compiled once, invalid patterns throw.
+  pub custom_extractors: Option<Vec<CustomExtractor>>,
+
+  /// Report parts of class names that are constructed at runtime in `diagnostics`, e.g.: the
+  /// `bg-` of `` `bg-${color}-500` ``
+  pub detect_dynamic_classes: Option<bool>,
 }
 
 #[derive(Debug, Clone)]
 #[napi]
 pub struct Scanner {
-  scanner: tailwindcss_oxide::Scanner,
+  /// Shared with asynchronous scans, which run on a worker thread
+  scanner: Arc<Mutex<tailwindcss_oxide::Scanner>>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct TransformInput {
+  /// The file of the content, not set for in-memory content
+  pub file: Option<String>,
+
+  pub content: String,
+}
+
+/// Wraps a JavaScript transformer, so that it always returns `(string | null)[]`. An empty array
+/// keeps the original content of the whole batch.
+const SAFE_TRANSFORMER: &str = r#"(transformer, extension) => (batch) => {
+  try {
+    let outputs = transformer(batch)
+    if (!Array.isArray(outputs)) {
+      throw new TypeError(`Expected an array of strings, got: ${Object.prototype.toString.call(outputs)}`)
+    }
+
+    return outputs.map((output) => (typeof output === 'string' ? output : null))
+  } catch (error) {
+    console.warn(`[tailwindcss] The transformer of \`.${extension}\` files failed, the original content is scanned instead:`, error)
+    return []
+  }
+}"#;
+
+/// A JavaScript transformer, called on the main thread.
+struct JsTransformer {
+  callback: ThreadsafeFunction<Vec<TransformInput>, ErrorStrategy::Fatal>,
+}
+
+impl tailwindcss_oxide::scanner::transformers::Transformer for JsTransformer {
+  fn transform(
+    &self,
+    batch: &[tailwindcss_oxide::scanner::transformers::TransformInput],
+  ) -> Vec<Option<String>> {
+    let batch = batch
+      .iter()
+      .map(|input| TransformInput {
+        file: input
+          .file
+          .as_ref()
+          .map(|file| file.to_string_lossy().into_owned()),
+        content: input.content.clone(),
+      })
+      .collect();
+
+    let (sender, receiver) = mpsc::channel();
+    let status = self.callback.call_with_return_value(
+      batch,
+      ThreadsafeFunctionCallMode::Blocking,
+      move |outputs: Option<Vec<Option<String>>>| {
+        _ = sender.send(outputs.unwrap_or_default());
+        Ok(())
+      },
+    );
+
+    // Keep the original content when the transformer can't be called, e.g.: during shutdown
+    if status != Status::Ok {
+      return vec![];
+    }
+
+    receiver.recv().unwrap_or_default()
+  }
+}
+
+/// Scans on a worker thread, so that the main thread is free to run transformers.
+pub struct ScanTask {
+  scanner: Arc<Mutex<tailwindcss_oxide::Scanner>>,
+
+  /// Content to scan, scans all files when not set
+  input: Option<Vec<tailwindcss_oxide::ChangedContent>>,
+}
+
+impl Task for ScanTask {
+  type Output = Vec<String>;
+  type JsValue = Vec<String>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let mut scanner = self
+      .scanner
+      .lock()
+      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+
+    Ok(match self.input.take() {
+      Some(input) => scanner.scan_content(input),
+      None => scanner.scan(),
+    })
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -94,74 +583,826 @@ pub struct CandidateWithPosition {
 
   /// The position of the candidate inside the content file
   pub position: i64,
+
+  /// The zero-based line of the candidate inside the content file
+  pub line: i64,
+
+  /// The zero-based column (in UTF-16 code units) of the candidate inside the line
+  pub column: i64,
+
+  /// The canonical spelling of the candidate, only set when `normalizeCandidates` is enabled and
+  /// it's spelled differently
+  pub canonical: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct TextEdit {
+  /// Byte offset where the replaced candidate starts
+  pub start: i64,
+
+  /// Byte offset where the replaced candidate ends
+  pub end: i64,
+
+  /// Position where the replaced candidate starts, in UTF-16 code units
+  pub utf16_start: i64,
+
+  /// Position where the replaced candidate ends, in UTF-16 code units
+  pub utf16_end: i64,
+
+  pub original: String,
+
+  pub replacement: String,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Rewritten {
+  /// The content with all replacements applied
+  pub content: String,
+
+  /// All applied edits, in order, with positions in the original content
+  pub edits: Vec<TextEdit>,
+}
+
+impl From<tailwindcss_oxide::rewrite::TextEdit> for TextEdit {
+  fn from(edit: tailwindcss_oxide::rewrite::TextEdit) -> Self {
+    Self {
+      start: edit.range.start as i64,
+      end: edit.range.end as i64,
+      utf16_start: edit.utf16_range.start as i64,
+      utf16_end: edit.utf16_range.end as i64,
+      original: edit.original,
+      replacement: edit.replacement,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct RenamePattern {
+  /// Regex that has to match the whole candidate, e.g.: `^!(.*)$`
+  pub pattern: String,
+
+  /// Replacement that can refer to capture groups, e.g.: `$1!`
+  pub replacement: String,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct RenameTable {
+  /// Candidates that are renamed as a whole, e.g.: `{ shadow: 'shadow-sm' }`
+  pub exact: Option<HashMap<String, String>>,
+
+  /// Patterns that are tried in order when there is no exact rename
+  pub patterns: Option<Vec<RenamePattern>>,
+}
+
+impl TryFrom<RenameTable> for tailwindcss_oxide::codemod::RenameTable {
+  type Error = Error;
+
+  fn try_from(renames: RenameTable) -> Result<Self> {
+    let patterns = renames
+      .patterns
+      .unwrap_or_default()
+      .into_iter()
+      .map(|rename| {
+        let pattern = regex::Regex::new(&rename.pattern).map_err(|e| {
+          Error::new(
+            Status::InvalidArg,
+            format!("Invalid pattern `{}`: {}", rename.pattern, e),
+          )
+        })?;
+
+        Ok((pattern, rename.replacement))
+      })
+      .collect::<Result<_>>()?;
+
+    Ok(Self {
+      exact: renames.exact.unwrap_or_default().into_iter().collect(),
+      patterns,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct FileChange {
+  pub file: String,
+
+  /// All applied edits, in order, with positions in the original content
+  pub edits: Vec<TextEdit>,
+
+  /// Unified diff of the changes, only set in dry-run mode
+  pub diff: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CodemodError {
+  pub file: String,
+
+  pub message: String,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Codemod {
+  /// Changes of all files that contain renamed candidates, sorted by file
+  pub changes: Vec<FileChange>,
+
+  /// Files that couldn't be read or written, sorted by file
+  pub errors: Vec<CodemodError>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ClassListSpan {
+  /// The position where the class list starts, without the quote
+  pub start: i64,
+
+  /// The position where the class list ends, without the quote
+  pub end: i64,
+
+  /// The quote around the class list, not set for Svelte class directives
+  pub quote: Option<String>,
+
+  /// Where the class list is written, one of `attribute`, `expression`, `helper`,
+  /// `tagged-template`, `keyword-argument` or `directive`
+  pub context: String,
+
+  /// The name of the attribute or helper, e.g.: `className` or `clsx`
+  pub name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateTypo {
+  /// The candidate as it was written, e.g.: `hover:bg-gren-500`
+  pub candidate: String,
+
+  /// The closest valid classes, e.g.: `hover:bg-green-500`
+  pub suggestions: Vec<String>,
+
+  /// The position of the candidate inside the content file, only set for a single file
+  pub position: Option<i64>,
+
+  /// The zero-based line of the candidate inside the content file, only set for a single file
+  pub line: Option<i64>,
+
+  /// The zero-based column (in UTF-16 code units) of the candidate inside the line, only set for
+  /// a single file
+  pub column: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateSpellings {
+  /// The canonical candidate
+  pub canonical: String,
+
+  /// All other spellings of the canonical candidate that were found
+  pub originals: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Diagnostic {
+  /// The file that contains the problem, not set for in-memory content
+  pub file: Option<String>,
+
+  /// Byte offset where the problem starts. For files with a transformer, offsets refer to the
+  /// transformed content.
+  pub start: i64,
+
+  /// Byte offset where the problem ends
+  pub end: i64,
+
+  /// A stable identifier of the kind of problem, e.g.: `unmatched-ignore-start`
+  pub code: String,
+
+  pub message: String,
+
+  /// The part of a class name that is constructed at runtime, e.g.: `bg-`, only set for
+  /// `dynamic-class` problems
+  pub fragment: Option<String>,
+}
+
+impl From<tailwindcss_oxide::diagnostics::Diagnostic> for Diagnostic {
+  fn from(diagnostic: tailwindcss_oxide::diagnostics::Diagnostic) -> Self {
+    Self {
+      file: diagnostic
+        .file
+        .map(|file| file.to_string_lossy().into_owned()),
+      start: diagnostic.range.start as i64,
+      end: diagnostic.range.end as i64,
+      code: diagnostic.kind.code().to_string(),
+      message: diagnostic.kind.to_string(),
+      fragment: match diagnostic.kind {
+        DiagnosticKind::DynamicClass { fragment } => Some(fragment),
+        _ => None,
+      },
+    }
+  }
+}
+
+#[napi(object)]
+pub struct CompactCandidates {
+  /// All candidates, encoded as UTF-8 and concatenated
+  pub data: Buffer,
+
+  /// The byte offsets where each candidate starts inside of `data`, followed by the total length.
+  /// Candidate `n` is `data[offsets[n]..offsets[n + 1]]`.
+  pub offsets: Uint32Array,
 }
 
 #[napi]
 impl Scanner {
   #[napi(constructor)]
-  pub fn new(opts: ScannerOptions) -> Self {
-    Self {
-      scanner: tailwindcss_oxide::Scanner::new(
+  pub fn new(opts: ScannerOptions) -> Result<Self> {
+    let custom_extractors = opts
+      .custom_extractors
+      .unwrap_or_default()
+      .into_iter()
+      .map(TryInto::try_into)
+      .collect::<Result<_>>()?;
+
+    Ok(Self {
+      scanner: Arc::new(Mutex::new(tailwindcss_oxide::Scanner::with_options(
         opts.detect_sources.map(Into::into),
         opts
           .sources
           .map(|x| x.into_iter().map(Into::into).collect()),
-      ),
+        tailwindcss_oxide::ScannerOptions {
+          skip_heuristics: opts.skip_heuristics.map(Into::into),
+          threads: opts.threads.map(|x| x as usize),
+          dedicated_thread_pool: opts.dedicated_thread_pool.unwrap_or(false),
+          design_system: opts.design_system.map(Into::into),
+          normalize_candidates: opts.normalize_candidates.unwrap_or(false),
+          decode_html_entities: opts.decode_html_entities.unwrap_or(false),
+          unescape_strings: opts.unescape_strings.unwrap_or_default(),
+          extract_stylesheets: opts.extract_stylesheets.unwrap_or(false),
+          strip_comments: opts.strip_comments.unwrap_or_default(),
+          strict_extraction: opts.strict_extraction.map(Into::into),
+          custom_extractors,
+          detect_dynamic_classes: opts.detect_dynamic_classes.unwrap_or(false),
+        },
+      ))),
+    })
+  }
+
+  #[napi(ts_return_type = "string[]")]
+  pub fn scan(&mut self, env: Env) -> Result<JsObject> {
+    // Build the JS array straight from the cached list of candidates, instead of copying all
+    // candidates into a `Vec<String>` first.
+    let candidates = self.scan_sync(&env, |scanner| scanner.scan_shared())?;
+    let mut result = env.create_array_with_length(candidates.len())?;
+
+    for (idx, candidate) in candidates.iter().enumerate() {
+      result.set_element(idx as u32, env.create_string(candidate)?)?;
     }
+
+    Ok(result)
   }
 
   #[napi]
-  pub fn scan(&mut self) -> Vec<String> {
-    self.scanner.scan()
+  pub fn scan_unfiltered(&mut self, env: Env) -> Result<Vec<String>> {
+    self.scan_sync(&env, |scanner| scanner.scan_unfiltered())
   }
 
+  /// Same as `scan`, but scans on a worker thread. Required to call transformers.
+  #[napi(ts_return_type = "Promise<string[]>")]
+  pub fn scan_async(&self) -> AsyncTask<ScanTask> {
+    AsyncTask::new(ScanTask {
+      scanner: self.scanner.clone(),
+      input: None,
+    })
+  }
+
+  /// Same as `scanFiles`, but scans on a worker thread. Required to call transformers.
+  #[napi(ts_return_type = "Promise<string[]>")]
+  pub fn scan_files_async(&self, input: Vec<ChangedContent>) -> Result<AsyncTask<ScanTask>> {
+    Ok(AsyncTask::new(ScanTask {
+      scanner: self.scanner.clone(),
+      input: Some(
+        input
+          .into_iter()
+          .map(TryInto::try_into)
+          .collect::<Result<_>>()?,
+      ),
+    }))
+  }
+
+  /// Transform the content of all files with the `extension` before extraction, e.g.: to compile
+  /// MDX with its real compiler. The transformer receives a batch of contents and returns the text
+  /// to extract from for each of them, or `null` to keep the original content. Pass `null` to
+  /// remove the transformer.
+  ///
+  /// Transformers are called on the main thread, so they are only called by `scanAsync` and
+  /// `scanFilesAsync`. Synchronous scans log a warning and scan the original content instead.
+  /// Diagnostics of transformed content refer to the transformed text. Transformers have to return
+  /// synchronously. When a transformer throws, or
+  /// returns anything else, e.g.: a `Promise`, a warning is logged and the original content of the
+  /// batch is scanned instead.
+  #[napi(
+    ts_args_type = "extension: string, transformer: ((batch: TransformInput[]) => Array<string | null | undefined>) | null"
+  )]
+  pub fn set_transformer(
+    &mut self,
+    env: Env,
+    extension: String,
+    transformer: Option<JsFunction>,
+  ) -> Result<()> {
+    let transformer = match transformer {
+      Some(transformer) => {
+        // Errors inside of threadsafe functions are fatal, so they are handled in JavaScript
+        let wrap: JsFunction = env.run_script(SAFE_TRANSFORMER)?;
+        let transformer: JsFunction = wrap
+          .call(
+            None,
+            &[
+              transformer.into_unknown(),
+              env.create_string(&extension)?.into_unknown(),
+            ],
+          )?
+          .try_into()?;
+
+        let mut callback: ThreadsafeFunction<Vec<TransformInput>, ErrorStrategy::Fatal> =
+          transformer
+            .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<TransformInput>>| {
+              Ok(vec![ctx.value])
+            })?;
+
+        // The transformer alone shouldn't keep the process alive
+        callback.unref(&env)?;
+
+        Some(Arc::new(JsTransformer { callback })
+          as Arc<
+            dyn tailwindcss_oxide::scanner::transformers::Transformer,
+          >)
+      }
+      None => None,
+    };
+
+    self.scanner()?.set_transformer(extension, transformer);
+    Ok(())
+  }
+
+  /// Update the design system, e.g.: when the CSS changed. Pass `null` to stop filtering.
   #[napi]
-  pub fn scan_files(&mut self, input: Vec<ChangedContent>) -> Vec<String> {
+  pub fn set_design_system(&mut self, design_system: Option<DesignSystem>) -> Result<()> {
     self
-      .scanner
-      .scan_content(input.into_iter().map(Into::into).collect())
+      .scanner()?
+      .set_design_system(design_system.map(Into::into));
+    Ok(())
+  }
+
+  /// Parse a candidate into its structured form, resolving roots with the design system.
+  #[napi]
+  pub fn parse_candidate(&self, candidate: String) -> Result<Option<ParsedCandidate>> {
+    Ok(self.scanner()?.parse_candidate(&candidate).map(Into::into))
+  }
+
+  #[napi]
+  pub fn scan_files(&mut self, env: Env, input: Vec<ChangedContent>) -> Result<Vec<String>> {
+    let (changed_content, buffers): (Vec<_>, Vec<_>) = input
+      .into_iter()
+      .partition(|x| !matches!(x.content, Some(Either::B(_))));
+
+    let changed_content = changed_content
+      .into_iter()
+      .map(TryInto::try_into)
+      .collect::<Result<_>>()?;
+
+    let contents: Vec<_> = buffers
+      .iter()
+      .filter_map(|x| match &x.content {
+        Some(Either::B(content)) => Some(tailwindcss_oxide::BorrowedContent {
+          content,
+          extension: Some(x.extension.as_str()).filter(|x| !x.is_empty()),
+          file: x.file.as_deref().map(Path::new),
+        }),
+        _ => None,
+      })
+      .collect();
+
+    self.scan_sync(&env, |scanner| {
+      let mut candidates = scanner.scan_content(changed_content);
+
+      if !contents.is_empty() {
+        // Both lists only contain new candidates, so they can't overlap.
+        candidates.extend(scanner.scan_borrowed(&contents));
+        candidates.sort();
+      }
+
+      candidates
+    })
+  }
+
+  /// Same as `scanFiles`, but returns the new candidates in a single buffer instead of an array of
+  /// strings.
+  #[napi]
+  pub fn scan_files_compact(
+    &mut self,
+    env: Env,
+    input: Vec<ChangedContent>,
+  ) -> Result<CompactCandidates> {
+    let candidates = self.scan_files(env, input)?;
+
+    let mut data = Vec::with_capacity(candidates.iter().map(|x| x.len()).sum());
+    let mut offsets = Vec::with_capacity(candidates.len() + 1);
+    for candidate in candidates {
+      offsets.push(data.len() as u32);
+      data.extend_from_slice(candidate.as_bytes());
+    }
+    offsets.push(data.len() as u32);
+
+    Ok(CompactCandidates {
+      data: data.into(),
+      offsets: offsets.into(),
+    })
   }
 
   #[napi]
   pub fn get_candidates_with_positions(
     &mut self,
     input: ChangedContent,
-  ) -> Vec<CandidateWithPosition> {
-    let content = input.content.unwrap_or_else(|| {
-      std::fs::read_to_string(&input.file.unwrap()).expect("Failed to read file")
-    });
+  ) -> Result<Vec<CandidateWithPosition>> {
+    if input.file.is_none() && input.content.is_none() {
+      return Ok(vec![]);
+    }
+
+    let (content, extension) = read_content(&input)?;
+    let utf16_idx = IndexConverter::new(&content);
+    let mut scanner = self.scanner()?;
+    let normalize_candidates = scanner.get_options().normalize_candidates;
 
-    let input = ChangedContent {
-      file: None,
-      content: Some(content.clone()),
-      extension: input.extension,
+    Ok(
+      scanner
+        .get_candidates_with_ranges(content.as_bytes(), extension)
+        .into_iter()
+        .map(|(candidate, range)| {
+          let (line, column) = utf16_idx.get_line_column(range.start);
+
+          CandidateWithPosition {
+            canonical: match normalize_candidates {
+              true => match normalize(&candidate) {
+                Cow::Owned(canonical) => Some(canonical),
+                Cow::Borrowed(_) => None,
+              },
+              false => None,
+            },
+            candidate,
+            position: utf16_idx.get(range.start),
+            line,
+            column,
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// Update the valid classes that typos are checked against, e.g.: when the CSS changed. Pass
+  /// `null` to stop checking for typos.
+  #[napi]
+  pub fn set_valid_classes(&mut self, classes: Option<Vec<String>>) -> Result<()> {
+    self.scanner()?.set_valid_classes(classes);
+    Ok(())
+  }
+
+  /// All scanned candidates that are close to, but not one of the valid classes.
+  #[napi(getter)]
+  pub fn typos(&self) -> Result<Vec<CandidateTypo>> {
+    Ok(
+      self
+        .scanner()?
+        .get_typos()
+        .into_iter()
+        .map(|typo| CandidateTypo {
+          candidate: typo.candidate,
+          suggestions: typo.suggestions,
+          position: None,
+          line: None,
+          column: None,
+        })
+        .collect(),
+    )
+  }
+
+  /// Same as `typos`, but for the candidates of a single file, together with their positions.
+  #[napi]
+  pub fn get_typos_with_positions(&mut self, input: ChangedContent) -> Result<Vec<CandidateTypo>> {
+    let (content, extension) = read_content(&input)?;
+    let utf16_idx = IndexConverter::new(&content);
+
+    Ok(
+      self
+        .scanner()?
+        .get_typos_with_ranges(content.as_bytes(), extension)
+        .into_iter()
+        .map(|(typo, range)| {
+          let (line, column) = utf16_idx.get_line_column(range.start);
+
+          CandidateTypo {
+            candidate: typo.candidate,
+            suggestions: typo.suggestions,
+            position: Some(utf16_idx.get(range.start)),
+            line: Some(line),
+            column: Some(column),
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// Replace candidates inside of a file, e.g.: for codemods. `replacements` maps candidates to
+  /// their replacement, or is a function that returns the replacement of a candidate, or
+  /// `null`/`undefined` to keep it.
+  #[napi(
+    ts_args_type = "input: ChangedContent, replacements: Record<string, string> | ((candidate: string) => string | null | undefined)"
+  )]
+  pub fn rewrite(
+    &mut self,
+    env: Env,
+    input: ChangedContent,
+    replacements: Either<JsFunction, HashMap<String, String>>,
+  ) -> Result<Rewritten> {
+    let (content, extension) = read_content(&input)?;
+    let mut scanner = self.scanner()?;
+
+    let rewritten = match replacements {
+      Either::A(callback) => {
+        // The first error of the callback is thrown once the rewrite is done
+        let mut error = None;
+        let rewritten = scanner.rewrite(&content, extension, |candidate| {
+          if error.is_some() {
+            return None;
+          }
+
+          let replacement = env
+            .create_string(candidate)
+            .and_then(|candidate| callback.call(None, &[candidate]))
+            .and_then(|value: JsUnknown| match value.get_type()? {
+              ValueType::String => Ok(Some(value.coerce_to_string()?.into_utf8()?.into_owned()?)),
+              _ => Ok(None),
+            });
+
+          replacement.unwrap_or_else(|e| {
+            error = Some(e);
+            None
+          })
+        });
+
+        if let Some(error) = error {
+          return Err(error);
+        }
+
+        rewritten
+      }
+      Either::B(replacements) => scanner.rewrite(&content, extension, |candidate| {
+        replacements.get(candidate).cloned()
+      }),
     };
 
-    let mut utf16_idx = IndexConverter::new(&content[..]);
+    Ok(Rewritten {
+      content: rewritten.content,
+      edits: rewritten.edits.into_iter().map(Into::into).collect(),
+    })
+  }
 
-    self
-      .scanner
-      .get_candidates_with_positions(input.into())
-      .into_iter()
-      .map(|(candidate, position)| CandidateWithPosition {
-        candidate,
-        position: utf16_idx.get(position),
+  /// Rename candidates in all resolved `files` in parallel, e.g.: when upgrading to a new major
+  /// version. Only candidates inside of class lists are replaced, e.g.: `class="…"` or `clsx(…)`,
+  /// all other text is kept as-is. Files are written atomically, unless `dryRun` is set, which
+  /// returns a unified diff per file instead.
+  #[napi]
+  pub fn codemod(&mut self, renames: RenameTable, dry_run: Option<bool>) -> Result<Codemod> {
+    let renames = renames.try_into()?;
+    let codemod = self.scanner()?.codemod(&renames, dry_run.unwrap_or(false));
+
+    Ok(Codemod {
+      changes: codemod
+        .changes
+        .into_iter()
+        .map(|change| FileChange {
+          file: change.file.to_string_lossy().into(),
+          edits: change.edits.into_iter().map(Into::into).collect(),
+          diff: change.diff,
+        })
+        .collect(),
+      errors: codemod
+        .errors
+        .into_iter()
+        .map(|(file, error)| CodemodError {
+          file: file.to_string_lossy().into(),
+          message: error.to_string(),
+        })
+        .collect(),
+    })
+  }
+
+  /// The full extent of all class lists inside of a file, e.g.: the value of `class="…"` or the
+  /// string arguments of `clsx(…)`. Positions are in UTF-16 code units, like `String#slice`.
+  #[napi]
+  pub fn get_class_list_spans(&self, input: ChangedContent) -> Result<Vec<ClassListSpan>> {
+    let (content, extension) = read_content(&input)?;
+    let utf16_idx = IndexConverter::new(&content);
+
+    Ok(
+      self
+        .scanner()?
+        .get_class_list_spans(content.as_bytes(), extension)
+        .into_iter()
+        .map(|span| ClassListSpan {
+          start: utf16_idx.get(span.range.start),
+          end: utf16_idx.get(span.range.end),
+          quote: span.quote.map(|quote| char::from(quote).to_string()),
+          context: span.context.kind().to_string(),
+          name: span.context.name().map(Into::into),
+        })
+        .collect(),
+    )
+  }
+
+  /// Update the order that class lists are sorted by, e.g.: when the CSS changed. Maps classes to
+  /// their sort key, e.g.: their index in the order of the compiler. Pass `null` to only remove
+  /// duplicates.
+  #[napi]
+  pub fn set_class_order(&mut self, order: Option<HashMap<String, i64>>) -> Result<()> {
+    let order = order
+      .map(|order| {
+        order
+          .into_iter()
+          .map(|(class, key)| {
+            let key = u64::try_from(key).map_err(|_| {
+              Error::new(
+                Status::InvalidArg,
+                format!("Invalid sort key `{}` of `{}`", key, class),
+              )
+            })?;
+
+            Ok((class, key))
+          })
+          .collect::<Result<_>>()
       })
-      .collect()
+      .transpose()?;
+
+    self.scanner()?.set_class_order(order);
+    Ok(())
+  }
+
+  /// Remove duplicate classes from all class lists inside of a file, and sort them by the class
+  /// order, with unknown classes first. Returns the new content, together with one edit per
+  /// changed class list.
+  #[napi]
+  pub fn normalize_class_lists(&self, input: ChangedContent) -> Result<Rewritten> {
+    let (content, extension) = read_content(&input)?;
+    let rewritten = self.scanner()?.normalize_class_lists(&content, extension);
+
+    Ok(Rewritten {
+      content: rewritten.content,
+      edits: rewritten.edits.into_iter().map(Into::into).collect(),
+    })
+  }
+
+  /// The byte ranges of all candidates inside of `content`, as `[start, end]` pairs.
+  #[napi]
+  pub fn get_candidate_ranges(
+    &mut self,
+    content: Uint8Array,
+    extension: Option<String>,
+  ) -> Result<Uint32Array> {
+    Ok(
+      self
+        .scanner()?
+        .get_candidate_ranges(&content, extension.as_deref())
+        .into_iter()
+        .flat_map(|range| [range.start as u32, range.end as u32])
+        .collect::<Vec<_>>()
+        .into(),
+    )
   }
 
   #[napi(getter)]
-  pub fn files(&mut self) -> Vec<String> {
-    self.scanner.get_files()
+  pub fn files(&mut self) -> Result<Vec<String>> {
+    Ok(self.scanner()?.get_files())
   }
 
   #[napi(getter)]
-  pub fn globs(&mut self) -> Vec<GlobEntry> {
-    self
-      .scanner
-      .get_globs()
-      .into_iter()
-      .map(Into::into)
-      .collect()
+  pub fn stats(&self) -> Result<ScanStats> {
+    Ok(self.scanner()?.get_stats().into())
+  }
+
+  #[napi(getter)]
+  pub fn spellings(&self) -> Result<Vec<CandidateSpellings>> {
+    Ok(
+      self
+        .scanner()?
+        .get_spellings()
+        .into_iter()
+        .map(|(canonical, originals)| CandidateSpellings {
+          canonical,
+          originals,
+        })
+        .collect(),
+    )
+  }
+
+  #[napi(getter)]
+  pub fn diagnostics(&self) -> Result<Vec<Diagnostic>> {
+    Ok(
+      self
+        .scanner()?
+        .get_diagnostics()
+        .into_iter()
+        .map(Into::into)
+        .collect(),
+    )
+  }
+
+  #[napi(getter)]
+  pub fn globs(&mut self) -> Result<Vec<GlobEntry>> {
+    Ok(
+      self
+        .scanner()?
+        .get_globs()
+        .into_iter()
+        .map(Into::into)
+        .collect(),
+    )
+  }
+}
+
+/// The content of a changed file, together with its extension.
+fn read_content(input: &ChangedContent) -> Result<(Cow<str>, Option<&str>)> {
+  let content: Cow<str> = match &input.content {
+    Some(Either::A(content)) => Cow::Borrowed(content),
+    Some(Either::B(content)) => Cow::Borrowed(std::str::from_utf8(content).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Content is not valid UTF-8: {}", e),
+      )
+    })?),
+    None => {
+      let file = input.file.as_ref().ok_or_else(|| {
+        Error::new(
+          Status::InvalidArg,
+          "Either `file` or `content` has to be set".to_string(),
+        )
+      })?;
+
+      Cow::Owned(std::fs::read_to_string(file).map_err(|e| {
+        Error::new(
+          Status::GenericFailure,
+          format!("Failed to read `{}`: {}", file, e),
+        )
+      })?)
+    }
+  };
+
+  let extension = Some(input.extension.as_str())
+    .filter(|x| !x.is_empty())
+    .or_else(|| {
+      input
+        .file
+        .as_deref()
+        .and_then(|file| std::path::Path::new(file).extension())
+        .and_then(|x| x.to_str())
+    });
+
+  Ok((content, extension))
+}
+
+impl Scanner {
+  /// The inner scanner, fails while an asynchronous scan is running instead of blocking the main
+  /// thread that the transformers of the scan need.
+  fn scanner(&self) -> Result<MutexGuard<'_, tailwindcss_oxide::Scanner>> {
+    self.scanner.try_lock().map_err(|_| {
+      Error::new(
+        Status::GenericFailure,
+        "The scanner is busy with an asynchronous scan".to_string(),
+      )
+    })
+  }
+
+  /// Run a synchronous scan, which can't call transformers. When transformers are registered, a
+  /// warning is logged and the original content is scanned instead.
+  fn scan_sync<R>(
+    &self,
+    env: &Env,
+    f: impl FnOnce(&mut tailwindcss_oxide::Scanner) -> R,
+  ) -> Result<R> {
+    let mut scanner = self.scanner()?;
+    if scanner.has_transformers() {
+      let console: JsObject = env.get_global()?.get_named_property("console")?;
+      let warn: JsFunction = console.get_named_property("warn")?;
+      warn.call(
+        Some(&console),
+        &[env.create_string(
+          "[tailwindcss] Transformers are only called by `scanAsync` and `scanFilesAsync`, the original content is scanned instead",
+        )?],
+      )?;
+    }
+
+    Ok(scanner.without_transformers(f))
   }
 }
@@ -1,50 +1,131 @@
+use std::ops::Range;
+
+/// Distance in bytes between two checkpoints
+const CHECKPOINT_STRIDE: usize = 64;
+
+/// A known UTF-8 byte index and its corresponding UTF-16 index. The UTF-8 index is always at a
+/// character boundary.
+#[derive(Clone, Copy, Debug, Default)]
+struct Checkpoint {
+  utf8: usize,
+  utf16: usize,
+}
+
 /// The `IndexConverter` is used to convert UTF-8 *BYTE* indexes to UTF-16
 /// *character* indexes
+///
+/// The input is decoded once up front into a sparse table of checkpoints (one for every
+/// `CHECKPOINT_STRIDE` bytes) and a list of non-ASCII runs. Lookups can happen in any order and
+/// only have to decode at most `CHECKPOINT_STRIDE` bytes. Inside of ASCII-only regions no
+/// decoding is necessary at all.
 #[derive(Clone)]
 pub struct IndexConverter<'a> {
   input: &'a str,
-  curr_utf8: usize,
-  curr_utf16: usize,
+
+  /// The checkpoint at or before every `n * CHECKPOINT_STRIDE` byte
+  checkpoints: Vec<Checkpoint>,
+
+  /// Byte ranges of consecutive non-ASCII characters, in order
+  non_ascii: Vec<Range<usize>>,
+
+  /// Byte indexes where each line starts
+  line_starts: Vec<usize>,
+
+  /// Length of the input in UTF-16 code units
+  len_utf16: usize,
 }
 
 impl<'a> IndexConverter<'a> {
   pub fn new(input: &'a str) -> Self {
+    let mut checkpoints = Vec::with_capacity(input.len() / CHECKPOINT_STRIDE + 1);
+    let mut non_ascii: Vec<Range<usize>> = vec![];
+    let mut line_starts = vec![0];
+    let mut utf16 = 0;
+
+    for (utf8, c) in input.char_indices() {
+      let len_utf8 = c.len_utf8();
+
+      // Every checkpoint position that falls inside of this character points to its start
+      while checkpoints.len() * CHECKPOINT_STRIDE < utf8 + len_utf8 {
+        checkpoints.push(Checkpoint { utf8, utf16 });
+      }
+
+      if c == '\n' {
+        line_starts.push(utf8 + 1);
+      } else if !c.is_ascii() {
+        match non_ascii.last_mut() {
+          Some(run) if run.end == utf8 => run.end += len_utf8,
+          _ => non_ascii.push(utf8..utf8 + len_utf8),
+        }
+      }
+
+      utf16 += c.len_utf16();
+    }
+
     Self {
       input,
-      curr_utf8: 0,
-      curr_utf16: 0,
+      checkpoints,
+      non_ascii,
+      line_starts,
+      len_utf16: utf16,
     }
   }
 
-  pub fn get(&mut self, pos: usize) -> i64 {
-    #[cfg(debug_assertions)]
-    if self.curr_utf8 > self.input.len() {
-      panic!("curr_utf8 points past the end of the input string");
+  /// Convert a UTF-8 byte index to a UTF-16 index. Indexes inside of a multi-byte character
+  /// resolve to the end of that character, indexes past the end resolve to the end of the input.
+  pub fn get(&self, pos: usize) -> i64 {
+    if pos >= self.input.len() {
+      return self.len_utf16 as i64;
     }
 
-    if pos < self.curr_utf8 {
-      self.curr_utf8 = 0;
-      self.curr_utf16 = 0;
+    // ASCII only input, the indexes are the same
+    if self.non_ascii.is_empty() {
+      return pos as i64;
     }
 
-    // SAFETY: No matter what `pos` is passed into this function `curr_utf8`
-    // will only ever be incremented up to the length of the input string.
+    let Checkpoint {
+      mut utf8,
+      mut utf16,
+    } = self.checkpoints[pos / CHECKPOINT_STRIDE];
+
+    // Nothing but ASCII between the checkpoint and the position
+    if !self.contains_non_ascii(utf8..pos) {
+      return (utf16 + pos - utf8) as i64;
+    }
+
+    // SAFETY: Checkpoints are always located at character boundaries of the input string.
     //
     // This eliminates a "potential" panic that cannot actually happen
-    let slice = unsafe {
-      self.input.get_unchecked(self.curr_utf8..)
-    };
+    let slice = unsafe { self.input.get_unchecked(utf8..) };
 
     for c in slice.chars() {
-      if self.curr_utf8 >= pos {
-        break
+      if utf8 >= pos {
+        break;
       }
 
-      self.curr_utf8 += c.len_utf8();
-      self.curr_utf16 += c.len_utf16();
+      utf8 += c.len_utf8();
+      utf16 += c.len_utf16();
     }
 
-    return self.curr_utf16 as i64;
+    utf16 as i64
+  }
+
+  /// Convert a UTF-8 byte index to a zero-based line and a zero-based UTF-16 column.
+  pub fn get_line_column(&self, pos: usize) -> (i64, i64) {
+    let pos = pos.min(self.input.len());
+    let line = self.line_starts.partition_point(|start| *start <= pos) - 1;
+    let column = self.get(pos) - self.get(self.line_starts[line]);
+
+    (line as i64, column)
+  }
+
+  fn contains_non_ascii(&self, range: Range<usize>) -> bool {
+    let idx = self.non_ascii.partition_point(|run| run.end <= range.start);
+
+    self
+      .non_ascii
+      .get(idx)
+      .map_or(false, |run| run.start < range.end)
   }
 }
 
@@ -55,7 +136,7 @@ mod test {
 
   #[test]
   fn test_index_converter() {
-    let mut converter = IndexConverter::new("Hello 🔥🥳 world!");
+    let converter = IndexConverter::new("Hello 🔥🥳 world!");
 
     let map = HashMap::from([
       // hello<space>
@@ -66,19 +147,16 @@ mod test {
       (4, 4),
       (5, 5),
       (6, 6),
-
       // inside the 🔥
       (7, 8),
       (8, 8),
       (9, 8),
       (10, 8),
-
       // inside the 🥳
       (11, 10),
       (12, 10),
       (13, 10),
       (14, 10),
-
       // <space>world!
       (15, 11),
       (16, 12),
@@ -87,7 +165,6 @@ mod test {
       (19, 15),
       (20, 16),
       (21, 17),
-
       // Past the end should return the last utf-16 character index
       (22, 17),
       (100, 17),
@@ -97,4 +174,43 @@ mod test {
       assert_eq!(converter.get(idx_utf8), idx_utf16);
     }
   }
+
+  #[test]
+  fn test_index_converter_matches_a_linear_scan_in_any_order() {
+    let input = "<div class=\"flex\">日本語 🔥 text</div>\n".repeat(50);
+    let converter = IndexConverter::new(&input);
+
+    // Map every byte index by walking the characters from the start
+    let mut expected = vec![];
+    let mut utf16 = 0;
+    for c in input.chars() {
+      for _ in 0..c.len_utf8() {
+        expected.push(utf16 + c.len_utf16());
+      }
+      utf16 += c.len_utf16();
+    }
+
+    // Character boundaries map to the start of the character itself
+    for (idx, _) in input.char_indices() {
+      expected[idx] = input[..idx].encode_utf16().count();
+    }
+
+    for idx in (0..input.len()).rev().step_by(7).chain(0..input.len()) {
+      assert_eq!(converter.get(idx), expected[idx] as i64, "at byte {}", idx);
+    }
+  }
+
+  #[test]
+  fn test_line_and_column() {
+    let converter = IndexConverter::new("flex\n🔥 underline\n\n日本 block");
+
+    assert_eq!(converter.get_line_column(0), (0, 0));
+    assert_eq!(converter.get_line_column(3), (0, 3));
+    assert_eq!(converter.get_line_column(5), (1, 0));
+    assert_eq!(converter.get_line_column(10), (1, 3));
+    assert_eq!(converter.get_line_column(20), (2, 0));
+    assert_eq!(converter.get_line_column(21), (3, 0));
+    assert_eq!(converter.get_line_column(28), (3, 3));
+    assert_eq!(converter.get_line_column(100), (3, 8));
+  }
 }
@@ -0,0 +1,58 @@
+import { Scanner } from '../index.js'
+import { expect, test, vi } from 'vitest'
+
+test('transformers replace the content before extraction', async () => {
+  let scanner = new Scanner({})
+  scanner.setTransformer('mdx', (batch) => batch.map(() => '<p class="flex">'))
+
+  expect(
+    await scanner.scanFilesAsync([{ content: '<p class="underline">', extension: 'mdx' }]),
+  ).toEqual(['class', 'flex', 'p'])
+})
+
+test('transformers that throw keep the original content', async () => {
+  let warn = vi.spyOn(console, 'warn').mockImplementation(() => {})
+
+  let scanner = new Scanner({})
+  scanner.setTransformer('mdx', () => {
+    throw new Error('Failed to compile')
+  })
+
+  expect(
+    await scanner.scanFilesAsync([{ content: '<p class="underline">', extension: 'mdx' }]),
+  ).toEqual(['class', 'p', 'underline'])
+  expect(warn).toHaveBeenCalledOnce()
+
+  warn.mockRestore()
+})
+
+test('transformers that return a promise keep the original content', async () => {
+  let warn = vi.spyOn(console, 'warn').mockImplementation(() => {})
+
+  let scanner = new Scanner({})
+  // @ts-expect-error Transformers have to return synchronously
+  scanner.setTransformer('mdx', async (batch) => batch.map(() => '<p class="flex">'))
+
+  expect(
+    await scanner.scanFilesAsync([{ content: '<p class="underline">', extension: 'mdx' }]),
+  ).toEqual(['class', 'p', 'underline'])
+  expect(warn).toHaveBeenCalledOnce()
+
+  warn.mockRestore()
+})
+
+test('synchronous scans keep the original content', () => {
+  let warn = vi.spyOn(console, 'warn').mockImplementation(() => {})
+
+  let scanner = new Scanner({})
+  scanner.setTransformer('mdx', (batch) => batch.map(() => '<p class="flex">'))
+
+  expect(scanner.scanFiles([{ content: '<p class="underline">', extension: 'mdx' }])).toEqual([
+    'class',
+    'p',
+    'underline',
+  ])
+  expect(warn).toHaveBeenCalledOnce()
+
+  warn.mockRestore()
+})
@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tailwindcss_oxide::parser::{Extractor, ExtractorOptions};
+
+// Candidates are converted to strings without checking, so every candidate has to be valid UTF-8,
+// no matter what bytes the input contains.
+fuzz_target!(|data: &[u8]| {
+    for preserve_spaces_in_arbitrary in [false, true] {
+        let opts = ExtractorOptions {
+            preserve_spaces_in_arbitrary,
+            ..Default::default()
+        };
+
+        for candidate in Extractor::all(data, opts) {
+            assert!(std::str::from_utf8(candidate).is_ok());
+        }
+    }
+});
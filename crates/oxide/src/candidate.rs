@@ -0,0 +1,910 @@
+use crate::design_system::CandidateFilter;
+use std::borrow::Cow;
+
+/// Where the important marker is written, e.g.: `!flex` or `flex!`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Important {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value<'a> {
+    /// A named value, e.g.: `red-500` in `bg-red-500`.
+    ///
+    /// When the value and a named modifier form a numeric ratio, the `fraction` contains both,
+    /// e.g.: `1/2` in `w-1/2`, but not in `bg-red-500/50`.
+    Named {
+        value: &'a str,
+        fraction: Option<&'a str>,
+    },
+
+    /// An arbitrary value, e.g.: `#0088cc` in `bg-[#0088cc]`, or `length:2px` in
+    /// `text-[length:2px]` with a `data_type` of `length`.
+    Arbitrary {
+        data_type: Option<&'a str>,
+        value: &'a str,
+    },
+
+    /// A CSS variable, e.g.: `--my-color` in `bg-(--my-color)` or `bg-[--my-color]`.
+    Var {
+        data_type: Option<&'a str>,
+        name: &'a str,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier<'a> {
+    /// e.g.: `50` in `bg-red-500/50`
+    Named(&'a str),
+
+    /// e.g.: `0.3` in `bg-red-500/[0.3]`
+    Arbitrary(&'a str),
+
+    /// e.g.: `--my-opacity` in `bg-red-500/(--my-opacity)`
+    Var(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant<'a> {
+    /// An arbitrary variant, e.g.: `&:hover` in `[&:hover]:flex`
+    Arbitrary { selector: &'a str },
+
+    /// A static, functional or compound variant, e.g.: `hover`, `data-[open]`, `group-hover` or
+    /// `group-hover/sidebar`
+    Named {
+        root: &'a str,
+        value: Option<Value<'a>>,
+        modifier: Option<Modifier<'a>>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utility<'a> {
+    /// An arbitrary property, e.g.: `[color:red]`
+    ArbitraryProperty {
+        property: &'a str,
+        value: &'a str,
+        modifier: Option<Modifier<'a>>,
+    },
+
+    /// A static or functional utility, e.g.: `flex`, `bg-red-500/50`
+    Named {
+        root: &'a str,
+        value: Option<Value<'a>>,
+        modifier: Option<Modifier<'a>>,
+    },
+}
+
+/// A structured representation of an extracted candidate.
+///
+/// Without a design system, the root of a utility or variant can't be known for sure, e.g.: it
+/// could be `translate` or `translate-x` in `translate-x-4`. In that case the root is everything
+/// up to the first `-`, or up to the arbitrary value if there is one. Use `Candidate::parse_with`
+/// to resolve roots using a design system instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate<'a> {
+    /// The candidate as it was written
+    pub raw: &'a str,
+
+    /// The prefix, only known when parsing with a design system, e.g.: `tw` in `tw:flex`
+    pub prefix: Option<&'a str>,
+
+    /// All variants in the order they were written, e.g.: `[md, hover]` in `md:hover:flex`
+    pub variants: Vec<Variant<'a>>,
+
+    pub utility: Utility<'a>,
+
+    pub important: Option<Important>,
+
+    /// Whether the utility is negated, e.g.: `-mx-4`
+    pub negative: bool,
+}
+
+impl<'a> Candidate<'a> {
+    /// Parse a candidate, returns `None` when the candidate is not syntactically valid.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        Self::parse_with(input, None)
+    }
+
+    /// Parse a candidate, and resolve the prefix and roots of utilities and variants with the
+    /// design system. Unknown roots fall back to the same rules as `Candidate::parse`.
+    pub fn parse_with(input: &'a str, filter: Option<&CandidateFilter>) -> Option<Self> {
+        if input.is_empty() || !is_balanced(input) {
+            return None;
+        }
+
+        let mut segments = split_top_level(input, b':');
+        let utility = segments.pop()?;
+
+        let mut prefix = None;
+        if let Some(expected) = filter.and_then(|filter| filter.prefix()) {
+            if segments.first() == Some(&expected) {
+                prefix = Some(segments.remove(0));
+            }
+        }
+
+        let variants = segments
+            .into_iter()
+            .map(|variant| parse_variant(variant, filter))
+            .collect::<Option<Vec<_>>>()?;
+
+        let (utility, important) = match (utility.strip_prefix('!'), utility.strip_suffix('!')) {
+            (Some(_), Some(_)) => return None,
+            (Some(utility), None) => (utility, Some(Important::Prefix)),
+            (None, Some(utility)) => (utility, Some(Important::Suffix)),
+            (None, None) => (utility, None),
+        };
+
+        let (utility, negative) = match utility.strip_prefix('-') {
+            Some(utility) => (utility, true),
+            None => (utility, false),
+        };
+
+        // Important marker after the negative sign, e.g.: `-!mx-4`
+        let (utility, important) = match (important, utility.strip_prefix('!')) {
+            (None, Some(utility)) if negative => (utility, Some(Important::Prefix)),
+            _ => (utility, important),
+        };
+
+        Some(Self {
+            raw: input,
+            prefix,
+            variants,
+            utility: parse_utility(utility, filter)?,
+            important,
+            negative,
+        })
+    }
+
+    /// The root of the utility, e.g.: `bg` in `hover:bg-red-500`
+    pub fn root(&self) -> Option<&'a str> {
+        match self.utility {
+            Utility::Named { root, .. } => Some(root),
+            Utility::ArbitraryProperty { .. } => None,
+        }
+    }
+}
+
+/// Map equivalent spellings of a candidate to one canonical spelling:
+///
+/// - The negative sign comes first, and the important marker is a suffix, e.g.: `!-mx-4`,
+///   `-!mx-4` and `!-mx-4` all become `-mx-4!`.
+/// - Whitespace in arbitrary values is written as a single `_`, e.g.: `bg-[rgba(0, 0, 0)]` and
+///   `bg-[rgba(0,__0,__0)]` both become `bg-[rgba(0,_0,_0)]`. Escaped underscores, e.g.: `\_`,
+///   and regions where underscores are literal, i.e.: `url(…)`, quoted strings and the name
+///   inside of `var(…)` and `theme(…)`, are kept as-is.
+/// - Strings in arbitrary values use single quotes, e.g.: `content-["hi"]` becomes
+///   `content-['hi']`, unless that requires escaping.
+///
+/// Candidates that are already canonical or that can't be parsed are returned as-is.
+pub fn normalize(input: &str) -> Cow<'_, str> {
+    let Some(candidate) = Candidate::parse(input) else {
+        return Cow::Borrowed(input);
+    };
+
+    let idx = find_last_top_level(input, b':').map_or(0, |idx| idx + 1);
+    let (variants, utility) = input.split_at(idx);
+
+    let mut body = utility;
+    body = body.strip_suffix('!').unwrap_or(body);
+    body = body.strip_prefix('!').unwrap_or(body);
+    body = body.strip_prefix('-').unwrap_or(body);
+    body = body.strip_prefix('!').unwrap_or(body);
+
+    let mut canonical = String::with_capacity(input.len());
+    canonical.push_str(variants);
+    if candidate.negative {
+        canonical.push('-');
+    }
+    canonical.push_str(body);
+    if candidate.important.is_some() {
+        canonical.push('!');
+    }
+
+    let canonical = normalize_arbitrary_values(&canonical);
+    match canonical == input {
+        true => Cow::Borrowed(input),
+        false => Cow::Owned(canonical),
+    }
+}
+
+/// Normalize the value of every top-level `[…]` of the candidate.
+fn normalize_arbitrary_values(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = String::with_capacity(input.len());
+    let mut last = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        if bytes[idx] != b'[' {
+            idx += 1;
+            continue;
+        }
+
+        let Some(end) = closing(bytes, idx, b'[', b']') else {
+            break;
+        };
+
+        output.push_str(&input[last..=idx]);
+        normalize_arbitrary_value(&input[idx + 1..end], &mut output);
+        last = end;
+        idx = end + 1;
+    }
+
+    output.push_str(&input[last..]);
+    output
+}
+
+/// Write the canonical spelling of an arbitrary value, in the same way the compiler decodes it,
+/// e.g.: `_` and spaces are both whitespace, but `\_` is a literal underscore.
+fn normalize_arbitrary_value(value: &str, output: &mut String) {
+    let bytes = value.as_bytes();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            // Escaped characters are kept, e.g.: `\_`
+            b'\\' => {
+                let len = value[idx + 1..].chars().next().map_or(0, char::len_utf8);
+                output.push_str(&value[idx..idx + 1 + len]);
+                idx += 1 + len;
+            }
+
+            // Underscores are literal inside of strings
+            quote @ (b'"' | b'\'') => {
+                let end = string_end(bytes, idx);
+                let string = &value[idx..end];
+                let inner = string
+                    .strip_prefix('"')
+                    .and_then(|string| string.strip_suffix('"'))
+                    .filter(|inner| !inner.contains(['\'', '\\']));
+
+                match (quote, inner) {
+                    (b'"', Some(inner)) => {
+                        output.push('\'');
+                        output.push_str(inner);
+                        output.push('\'');
+                    }
+                    _ => output.push_str(string),
+                }
+                idx = end;
+            }
+
+            b'(' => {
+                let name_start = output
+                    .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                    .map_or(0, |idx| idx + 1);
+                let name = &output[name_start..];
+
+                // Underscores are literal inside of `url(…)`, and in the name inside of `var(…)`
+                let end = if name == "url" || name.ends_with("_url") {
+                    closing(bytes, idx, b'(', b')').map_or(bytes.len(), |end| end + 1)
+                } else if ["var", "theme"]
+                    .iter()
+                    .any(|x| name == *x || name.ends_with(&format!("_{}", x)))
+                {
+                    first_argument_end(bytes, idx)
+                } else {
+                    idx + 1
+                };
+
+                output.push_str(&value[idx..end]);
+                idx = end;
+            }
+
+            b' ' | b'_' => {
+                while idx < bytes.len() && matches!(bytes[idx], b' ' | b'_') {
+                    idx += 1;
+                }
+                output.push('_');
+            }
+
+            _ => {
+                let len = value[idx..].chars().next().map_or(1, char::len_utf8);
+                output.push_str(&value[idx..idx + len]);
+                idx += len;
+            }
+        }
+    }
+}
+
+/// The position after the string that starts at `start`, or the end of the input.
+fn string_end(input: &[u8], start: usize) -> usize {
+    let mut idx = start + 1;
+    while idx < input.len() {
+        match input[idx] {
+            b'\\' => idx += 2,
+            c if c == input[start] => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+
+    input.len()
+}
+
+/// The position of the bracket that closes the one at `start`, skipping over strings.
+fn closing(input: &[u8], start: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut idx = start;
+    while idx < input.len() {
+        match input[idx] {
+            b'\\' => idx += 1,
+            b'"' | b'\'' => {
+                idx = string_end(input, idx);
+                continue;
+            }
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    None
+}
+
+/// The position where the first argument of the function whose `(` is at `start` ends.
+fn first_argument_end(input: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut idx = start + 1;
+    while idx < input.len() {
+        match input[idx] {
+            b'\\' => idx += 1,
+            b'(' => depth += 1,
+            b')' if depth == 0 => return idx,
+            b')' => depth -= 1,
+            b',' if depth == 0 => return idx,
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    input.len()
+}
+
+fn parse_utility<'a>(input: &'a str, filter: Option<&CandidateFilter>) -> Option<Utility<'a>> {
+    let (body, modifier) = split_modifier(input)?;
+
+    if let Some(inner) = strip_wrapper(body, '[', ']') {
+        let colon = find_top_level(inner, b':')?;
+        let (property, value) = (&inner[..colon], &inner[colon + 1..]);
+        if property.is_empty() || value.is_empty() {
+            return None;
+        }
+
+        return Some(Utility::ArbitraryProperty {
+            property,
+            value,
+            modifier,
+        });
+    }
+
+    let root = filter
+        .and_then(|filter| filter.utility_root(body))
+        .or_else(|| guess_root(body))?;
+    let value = parse_value(input, root, modifier, true)?;
+
+    Some(Utility::Named {
+        root,
+        value,
+        modifier,
+    })
+}
+
+fn parse_variant<'a>(input: &'a str, filter: Option<&CandidateFilter>) -> Option<Variant<'a>> {
+    if let Some(selector) = strip_wrapper(input, '[', ']') {
+        return (!selector.is_empty()).then_some(Variant::Arbitrary { selector });
+    }
+
+    let (body, modifier) = split_modifier(input)?;
+
+    let root = filter
+        .and_then(|filter| filter.variant_root(body))
+        .or_else(|| match body.strip_prefix('@') {
+            // Container query variants, e.g.: `@md` or `@[400px]`
+            Some(rest) if !rest.is_empty() => Some(&body[..1]),
+            _ => guess_root(body),
+        })?;
+    let value = parse_value(input, root, modifier, false)?;
+
+    Some(Variant::Named {
+        root,
+        value,
+        modifier,
+    })
+}
+
+/// Parse the value that follows the `root` in `input`. Returns `Some(None)` for bare roots, and
+/// `None` for invalid values.
+fn parse_value<'a>(
+    input: &'a str,
+    root: &'a str,
+    modifier: Option<Modifier<'a>>,
+    allow_fraction: bool,
+) -> Option<Option<Value<'a>>> {
+    let rest = &input[root.len()..];
+    let with_modifier = rest.strip_prefix('-').unwrap_or(rest);
+    let value = match modifier {
+        Some(_) => &with_modifier[..find_last_top_level(with_modifier, b'/')?],
+        None => with_modifier,
+    };
+
+    if value.is_empty() {
+        // A `-` without a value after it, e.g.: `bg-`
+        return (rest.len() == with_modifier.len()).then_some(None);
+    }
+
+    if let Some(inner) = strip_wrapper(value, '[', ']') {
+        let (data_type, inner) = split_data_type(inner);
+        if inner.is_empty() {
+            return None;
+        }
+
+        return Some(Some(match inner.starts_with("--") {
+            true => Value::Var {
+                data_type,
+                name: inner,
+            },
+            false => Value::Arbitrary {
+                data_type,
+                value: inner,
+            },
+        }));
+    }
+
+    if let Some(inner) = strip_wrapper(value, '(', ')') {
+        let (data_type, name) = split_data_type(inner);
+        return name
+            .starts_with("--")
+            .then_some(Some(Value::Var { data_type, name }));
+    }
+
+    if value.contains(['[', ']', '(', ')']) {
+        return None;
+    }
+
+    Some(Some(Value::Named {
+        value,
+        fraction: match modifier {
+            Some(Modifier::Named(modifier))
+                if allow_fraction && is_number(value) && is_number(modifier) =>
+            {
+                Some(with_modifier)
+            }
+            _ => None,
+        },
+    }))
+}
+
+/// Whether the input is a positive number, e.g.: `1` or `2.5`.
+fn is_number(input: &str) -> bool {
+    let (integer, fraction) = input.split_once('.').unwrap_or((input, "0"));
+    !integer.is_empty()
+        && !fraction.is_empty()
+        && integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|c| c.is_ascii_digit())
+}
+
+/// Split the modifier from the input, e.g.: `bg-red-500/50` becomes `bg-red-500` and `50`.
+fn split_modifier(input: &str) -> Option<(&str, Option<Modifier>)> {
+    let Some(idx) = find_last_top_level(input, b'/') else {
+        return Some((input, None));
+    };
+
+    let (body, modifier) = (&input[..idx], &input[idx + 1..]);
+    if body.is_empty() || modifier.is_empty() {
+        return None;
+    }
+
+    let modifier = if let Some(inner) = strip_wrapper(modifier, '[', ']') {
+        match inner.starts_with("--") {
+            true => Modifier::Var(inner),
+            false => Modifier::Arbitrary(inner),
+        }
+    } else if let Some(inner) = strip_wrapper(modifier, '(', ')') {
+        Modifier::Var(inner.starts_with("--").then_some(inner)?)
+    } else if modifier.contains(['[', ']', '(', ')']) {
+        return None;
+    } else {
+        Modifier::Named(modifier)
+    };
+
+    Some((body, Some(modifier)))
+}
+
+/// Guess the root without a design system: everything up to the arbitrary value, or up to the
+/// first `-`, e.g.: `bg` in `bg-[#0088cc]` and `bg-red-500`.
+fn guess_root(input: &str) -> Option<&str> {
+    let root = match input.find(['[', '(']) {
+        Some(idx) => input[..idx].strip_suffix('-')?,
+        None => input.split('-').next()?,
+    };
+
+    (!root.is_empty()).then_some(root)
+}
+
+/// Split a type hint from an arbitrary value, e.g.: `length:2px` becomes `length` and `2px`.
+fn split_data_type(input: &str) -> (Option<&str>, &str) {
+    match input.split_once(':') {
+        Some((data_type, value))
+            if !data_type.is_empty()
+                && data_type
+                    .bytes()
+                    .all(|c| c.is_ascii_lowercase() || c == b'-') =>
+        {
+            (Some(data_type), value)
+        }
+        _ => (None, input),
+    }
+}
+
+fn strip_wrapper(input: &str, open: char, close: char) -> Option<&str> {
+    let inner = input.strip_prefix(open)?.strip_suffix(close)?;
+
+    // Ensure that the wrapper belongs together, e.g.: `[a]-[b]` is not wrapped.
+    is_balanced(inner).then_some(inner)
+}
+
+fn is_balanced(input: &str) -> bool {
+    let mut stack = vec![];
+
+    for c in input.bytes() {
+        match c {
+            b'[' | b'(' => stack.push(c),
+            b']' if stack.pop() != Some(b'[') => return false,
+            b')' if stack.pop() != Some(b'(') => return false,
+            _ => {}
+        }
+    }
+
+    stack.is_empty()
+}
+
+fn find_top_level(input: &str, separator: u8) -> Option<usize> {
+    top_level_positions(input, separator).next()
+}
+
+fn find_last_top_level(input: &str, separator: u8) -> Option<usize> {
+    top_level_positions(input, separator).last()
+}
+
+fn top_level_positions(input: &str, separator: u8) -> impl Iterator<Item = usize> + '_ {
+    let mut depth = 0usize;
+
+    input
+        .bytes()
+        .enumerate()
+        .filter_map(move |(idx, c)| match c {
+            b'[' | b'(' => {
+                depth += 1;
+                None
+            }
+            b']' | b')' => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            c if c == separator && depth == 0 => Some(idx),
+            _ => None,
+        })
+}
+
+/// Split the input on `separator`, ignoring separators inside of brackets and parens.
+pub(crate) fn split_top_level(input: &str, separator: u8) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+
+    for idx in top_level_positions(input, separator) {
+        parts.push(&input[start..idx]);
+        start = idx + 1;
+    }
+
+    parts.push(&input[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::design_system::DesignSystem;
+
+    #[test]
+    fn it_should_parse_static_utilities() {
+        let candidate = Candidate::parse("flex").unwrap();
+
+        assert_eq!(candidate.raw, "flex");
+        assert_eq!(candidate.variants, vec![]);
+        assert_eq!(
+            candidate.utility,
+            Utility::Named {
+                root: "flex",
+                value: None,
+                modifier: None
+            }
+        );
+        assert_eq!(candidate.important, None);
+        assert!(!candidate.negative);
+    }
+
+    #[test]
+    fn it_should_parse_functional_utilities() {
+        assert_eq!(
+            Candidate::parse("bg-red-500/50").unwrap().utility,
+            Utility::Named {
+                root: "bg",
+                value: Some(Value::Named {
+                    value: "red-500",
+                    fraction: None
+                }),
+                modifier: Some(Modifier::Named("50"))
+            }
+        );
+
+        assert_eq!(
+            Candidate::parse("w-1/2").unwrap().utility,
+            Utility::Named {
+                root: "w",
+                value: Some(Value::Named {
+                    value: "1",
+                    fraction: Some("1/2")
+                }),
+                modifier: Some(Modifier::Named("2"))
+            }
+        );
+
+        assert_eq!(
+            Candidate::parse("aspect-2.5/1").unwrap().utility,
+            Utility::Named {
+                root: "aspect",
+                value: Some(Value::Named {
+                    value: "2.5",
+                    fraction: Some("2.5/1")
+                }),
+                modifier: Some(Modifier::Named("1"))
+            }
+        );
+
+        assert_eq!(
+            Candidate::parse("bg-[url(/img/a.png)]/[0.3]")
+                .unwrap()
+                .utility,
+            Utility::Named {
+                root: "bg",
+                value: Some(Value::Arbitrary {
+                    data_type: None,
+                    value: "url(/img/a.png)"
+                }),
+                modifier: Some(Modifier::Arbitrary("0.3"))
+            }
+        );
+
+        assert_eq!(
+            Candidate::parse("text-[length:2px]").unwrap().utility,
+            Utility::Named {
+                root: "text",
+                value: Some(Value::Arbitrary {
+                    data_type: Some("length"),
+                    value: "2px"
+                }),
+                modifier: None
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_not_parse_opacity_modifiers_as_fractions() {
+        for input in [
+            "bg-red-500/50",
+            "text-white/75",
+            "bg-black/[0.5]",
+            "w-full/2",
+        ] {
+            match Candidate::parse(input).unwrap().utility {
+                Utility::Named {
+                    value: Some(Value::Named { fraction, .. }),
+                    ..
+                } => assert_eq!(fraction, None, "{input}"),
+                utility => panic!("Unexpected utility for {input}: {utility:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_parse_css_variables() {
+        for input in ["bg-(--my-color)", "bg-[--my-color]"] {
+            assert_eq!(
+                Candidate::parse(input).unwrap().utility,
+                Utility::Named {
+                    root: "bg",
+                    value: Some(Value::Var {
+                        data_type: None,
+                        name: "--my-color"
+                    }),
+                    modifier: None
+                }
+            );
+        }
+
+        assert_eq!(
+            Candidate::parse("bg-(color:--my-color)/(--alpha)")
+                .unwrap()
+                .utility,
+            Utility::Named {
+                root: "bg",
+                value: Some(Value::Var {
+                    data_type: Some("color"),
+                    name: "--my-color"
+                }),
+                modifier: Some(Modifier::Var("--alpha"))
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_parse_arbitrary_properties() {
+        assert_eq!(
+            Candidate::parse("[mask-type:luminance]/50")
+                .unwrap()
+                .utility,
+            Utility::ArbitraryProperty {
+                property: "mask-type",
+                value: "luminance",
+                modifier: Some(Modifier::Named("50"))
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_parse_important_and_negative() {
+        let candidate = Candidate::parse("!-mx-4").unwrap();
+        assert_eq!(candidate.important, Some(Important::Prefix));
+        assert!(candidate.negative);
+        assert_eq!(candidate.root(), Some("mx"));
+
+        let candidate = Candidate::parse("-mx-4!").unwrap();
+        assert_eq!(candidate.important, Some(Important::Suffix));
+        assert!(candidate.negative);
+
+        let candidate = Candidate::parse("-!mx-4").unwrap();
+        assert_eq!(candidate.important, Some(Important::Prefix));
+        assert!(candidate.negative);
+        assert_eq!(candidate.root(), Some("mx"));
+
+        assert_eq!(Candidate::parse("!flex!"), None);
+    }
+
+    #[test]
+    fn it_should_parse_variants() {
+        let candidate =
+            Candidate::parse("[&:hover]:group-hover/sidebar:data-[state=open]:@md:flex").unwrap();
+
+        assert_eq!(
+            candidate.variants,
+            vec![
+                Variant::Arbitrary {
+                    selector: "&:hover"
+                },
+                Variant::Named {
+                    root: "group",
+                    value: Some(Value::Named {
+                        value: "hover",
+                        fraction: None
+                    }),
+                    modifier: Some(Modifier::Named("sidebar"))
+                },
+                Variant::Named {
+                    root: "data",
+                    value: Some(Value::Arbitrary {
+                        data_type: None,
+                        value: "state=open"
+                    }),
+                    modifier: None
+                },
+                Variant::Named {
+                    root: "@",
+                    value: Some(Value::Named {
+                        value: "md",
+                        fraction: None
+                    }),
+                    modifier: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_resolve_roots_with_a_design_system() {
+        let filter = CandidateFilter::new(&DesignSystem {
+            utilities: vec!["translate".into(), "translate-x".into()],
+            static_utilities: vec!["sr-only".into()],
+            variants: vec!["peer-focus".into()],
+            prefix: Some("tw".into()),
+        });
+
+        let candidate =
+            Candidate::parse_with("tw:peer-focus:translate-x-4", Some(&filter)).unwrap();
+        assert_eq!(candidate.prefix, Some("tw"));
+        assert_eq!(
+            candidate.variants,
+            vec![Variant::Named {
+                root: "peer-focus",
+                value: None,
+                modifier: None
+            }]
+        );
+        assert_eq!(candidate.root(), Some("translate-x"));
+
+        let candidate = Candidate::parse_with("tw:sr-only", Some(&filter)).unwrap();
+        assert_eq!(candidate.root(), Some("sr-only"));
+
+        // Without a design system, the first segment is the root
+        assert_eq!(
+            Candidate::parse("translate-x-4").unwrap().root(),
+            Some("translate")
+        );
+    }
+
+    #[test]
+    fn it_should_normalize_candidates() {
+        for (input, expected) in [
+            ("underline!", "underline!"),
+            ("!underline", "underline!"),
+            ("hover:!underline", "hover:underline!"),
+            ("!-m-4", "-m-4!"),
+            ("-!m-4", "-m-4!"),
+            ("-m-4!", "-m-4!"),
+            ("![foo:bar]", "[foo:bar]!"),
+            ("bg-[rgba(0, 0, 0)]", "bg-[rgba(0,_0,_0)]"),
+            ("bg-[rgba(0,__0,_0)]", "bg-[rgba(0,_0,_0)]"),
+            ("[&_>_*]:flex", "[&_>_*]:flex"),
+            ("[&  >  *]:flex", "[&_>_*]:flex"),
+            ("[&__>__*]:flex", "[&_>_*]:flex"),
+            ("[&\\_p]:flex", "[&\\_p]:flex"),
+            ("bg-[url(/my__image.png)]", "bg-[url(/my__image.png)]"),
+            ("bg-[url('/my  image.png')]", "bg-[url('/my  image.png')]"),
+            (
+                "w-[calc(var(--my__width)__+__1rem)]",
+                "w-[calc(var(--my__width)_+_1rem)]",
+            ),
+            ("content-['hello__world']", "content-['hello__world']"),
+            ("content-[\"hi\"]", "content-['hi']"),
+            ("content-[\"it's\"]", "content-[\"it's\"]"),
+            ("[data-state=\"open\"]:flex", "[data-state='open']:flex"),
+            ("bg-red-500/[0.5]", "bg-red-500/[0.5]"),
+            ("bg-[", "bg-["),
+        ] {
+            assert_eq!(normalize(input), expected, "{}", input);
+        }
+
+        assert!(matches!(normalize("hover:underline!"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn it_should_reject_invalid_candidates() {
+        for input in [
+            "",
+            "bg-[red",
+            "bg-red]",
+            "bg-[a)",
+            "hover:",
+            ":flex",
+            "bg-red-500/",
+            "/50",
+            "[color]",
+            "[:red]",
+            "bg-[]",
+            "bg-(my-color)",
+            "bg[red]",
+            "[]:flex",
+        ] {
+            assert_eq!(Candidate::parse(input), None, "{} should be invalid", input);
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use std::ops::Range;
+
+/// Attributes that contain classes, together with whether their value is an expression, e.g.:
+/// `:class="{ 'font-bold': active }"`, instead of a plain class list, e.g.: `class="flex"`.
+///
+/// Longer names come first, e.g.: `class:list` before `class`.
+const ATTRIBUTES: &[(&[u8], bool)] = &[
+    (b"className", false),
+    (b"class:list", true),
+    (b"v-bind:class", true),
+    (b":class", true),
+    (b"class", false),
+    (b"tw", false),
+];
+
+/// Where a class list is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassListContext {
+    /// The value of an attribute, e.g.: `class="flex"`
+    Attribute { name: String },
+
+    /// A string inside of an attribute expression, e.g.: `'font-bold'` in
+    /// `:class="{ 'font-bold': active }"` or `className={a ? "flex" : "hidden"}`
+    Expression { name: String },
+
+    /// A string argument of a helper, e.g.: `clsx("flex", …)`
+    Helper { name: String },
+
+    /// A tagged template, e.g.: tw`flex`
+    TaggedTemplate { name: String },
+
+    /// The keyword argument of a template helper, e.g.: `class: "flex"` in Ruby
+    KeywordArgument { name: String },
+
+    /// A Svelte class directive, e.g.: `active` in `class:active={…}`
+    Directive,
+}
+
+impl ClassListContext {
+    /// A stable identifier of the context, e.g.: `attribute`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClassListContext::Attribute { .. } => "attribute",
+            ClassListContext::Expression { .. } => "expression",
+            ClassListContext::Helper { .. } => "helper",
+            ClassListContext::TaggedTemplate { .. } => "tagged-template",
+            ClassListContext::KeywordArgument { .. } => "keyword-argument",
+            ClassListContext::Directive => "directive",
+        }
+    }
+
+    /// The name of the attribute or helper, e.g.: `className` or `clsx`
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            ClassListContext::Attribute { name }
+            | ClassListContext::Expression { name }
+            | ClassListContext::Helper { name }
+            | ClassListContext::TaggedTemplate { name }
+            | ClassListContext::KeywordArgument { name } => Some(name),
+            ClassListContext::Directive => None,
+        }
+    }
+}
+
+/// The full extent of a class list, e.g.: the `flex underline` inside of `class="flex underline"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassListSpan {
+    /// Byte range of the class list, without the quotes
+    pub range: Range<usize>,
+
+    /// The quote around the class list, not set for Svelte class directives
+    pub quote: Option<u8>,
+
+    pub context: ClassListContext,
+}
+
+/// All class lists in markup and scripts, e.g.: `class="flex underline"`, or the string arguments
+/// of `helpers`, e.g.: `clsx("flex", a && "b")`, in order.
+///
+/// Expressions, e.g.: `className={…}`, contribute all of their string literals. Template literals
+/// are a single span including their interpolations, the string literals inside of the
+/// interpolations are spans of their own.
+pub fn class_list_spans(input: &[u8], helpers: &[String]) -> Vec<ClassListSpan> {
+    let mut spans = vec![];
+    let mut idx = 0;
+
+    while idx < input.len() {
+        if idx > 0 && is_identifier(input[idx - 1]) {
+            idx += 1;
+            continue;
+        }
+
+        let rest = &input[idx..];
+
+        // Helper calls, e.g.: `clsx(…)`, or tagged templates, e.g.: tw`…`
+        if let Some(helper) = helpers
+            .iter()
+            .find(|helper| rest.starts_with(helper.as_bytes()))
+        {
+            let open = idx + helper.len();
+            match input.get(open) {
+                Some(b'(') => {
+                    let end = closing(input, open, b'(', b')');
+                    let context = ClassListContext::Helper {
+                        name: helper.clone(),
+                    };
+                    strings(input, open + 1..end, &context, &mut spans);
+                    idx = end;
+                    continue;
+                }
+                Some(b'`') => {
+                    let context = ClassListContext::TaggedTemplate {
+                        name: helper.clone(),
+                    };
+                    idx = string(input, open, &context, &mut spans);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // Svelte class directives, e.g.: `class:active={…}`
+        if rest.starts_with(b"class:") && !rest.starts_with(b"class:list") {
+            let start = idx + b"class:".len();
+            let end = input[start..]
+                .iter()
+                .position(|c| !is_identifier(*c) || *c == b':')
+                .map_or(input.len(), |len| start + len);
+
+            if end > start && input.get(end).map_or(true, |c| b"= \t\r\n/>".contains(c)) {
+                spans.push(ClassListSpan {
+                    range: start..end,
+                    quote: None,
+                    context: ClassListContext::Directive,
+                });
+                idx = end;
+                continue;
+            }
+        }
+
+        let Some((name, expression)) = ATTRIBUTES.iter().find(|(name, _)| rest.starts_with(name))
+        else {
+            idx += 1;
+            continue;
+        };
+
+        let mut value = idx + name.len();
+
+        // Keyword arguments of template helpers, e.g.: `class: "flex"` in Ruby
+        let keyword = input.get(value) == Some(&b':')
+            && input.get(value + 1).is_some_and(u8::is_ascii_whitespace);
+
+        if keyword {
+            value += 1;
+        } else if input.get(value).is_some_and(|c| is_identifier(*c)) {
+            idx = value;
+            continue;
+        }
+
+        value += whitespace(&input[value..]);
+        if !keyword {
+            if input.get(value) != Some(&b'=') {
+                idx = value;
+                continue;
+            }
+
+            value += 1;
+            value += whitespace(&input[value..]);
+        }
+
+        let name = String::from_utf8_lossy(name).into_owned();
+        let expression_context = ClassListContext::Expression { name: name.clone() };
+
+        idx = match input.get(value) {
+            Some(quote @ (b'"' | b'\'')) if !keyword || !expression => {
+                let end = input[value + 1..]
+                    .iter()
+                    .position(|c| c == quote)
+                    .map_or(input.len(), |len| value + 1 + len);
+
+                match expression {
+                    true => strings(input, value + 1..end, &expression_context, &mut spans),
+                    false => spans.push(ClassListSpan {
+                        range: value + 1..end,
+                        quote: Some(*quote),
+                        context: match keyword {
+                            true => ClassListContext::KeywordArgument { name },
+                            false => ClassListContext::Attribute { name },
+                        },
+                    }),
+                }
+
+                end + 1
+            }
+            Some(b'{') if !keyword => {
+                let end = closing(input, value, b'{', b'}');
+                strings(input, value + 1..end, &expression_context, &mut spans);
+                end
+            }
+            _ => value,
+        };
+    }
+
+    spans.sort_by_key(|span| span.range.start);
+    spans
+}
+
+/// The ranges of all class lists, like `class_list_spans`, but only with the static parts of
+/// template literals, e.g.: `flex ` and ` p-4` in `` `flex ${a ? 'b' : 'c'} p-4` ``.
+pub(crate) fn class_list_ranges(input: &[u8], helpers: &[String]) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+
+    for span in class_list_spans(input, helpers) {
+        if span.quote != Some(b'`') {
+            ranges.push(span.range);
+            continue;
+        }
+
+        let mut segment = span.range.start;
+        let mut idx = span.range.start;
+        while idx < span.range.end {
+            match input[idx] {
+                b'\\' => idx += 2,
+                b'$' if input.get(idx + 1) == Some(&b'{') => {
+                    ranges.push(segment..idx);
+                    idx = closing(input, idx + 1, b'{', b'}') + 1;
+                    segment = idx;
+                }
+                _ => idx += 1,
+            }
+        }
+
+        ranges.push(segment.min(span.range.end)..span.range.end);
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    ranges
+}
+
+/// Characters that can be part of attribute and function names.
+fn is_identifier(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'$' | b':')
+}
+
+fn whitespace(input: &[u8]) -> usize {
+    input.iter().take_while(|c| c.is_ascii_whitespace()).count()
+}
+
+/// The position of the bracket that closes the one at `start`, skipping over string literals. Falls
+/// back to the end of the input.
+fn closing(input: &[u8], start: usize, open: u8, close: u8) -> usize {
+    let mut depth = 0;
+    let mut idx = start;
+
+    while idx < input.len() {
+        match input[idx] {
+            b'"' | b'\'' | b'`' => {
+                idx = string(input, idx, &ClassListContext::Directive, &mut vec![]);
+                continue;
+            }
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return idx;
+                }
+            }
+            _ => {}
+        }
+
+        idx += 1;
+    }
+
+    input.len()
+}
+
+/// Collect all string literals in `range` of a script expression.
+fn strings(
+    input: &[u8],
+    range: Range<usize>,
+    context: &ClassListContext,
+    spans: &mut Vec<ClassListSpan>,
+) {
+    let mut idx = range.start;
+
+    while idx < range.end {
+        match input[idx] {
+            b'"' | b'\'' | b'`' => idx = string(input, idx, context, spans),
+            _ => idx += 1,
+        }
+    }
+}
+
+/// Collect the string literal that starts at `start`, and return its end. The strings of the
+/// interpolations of template literals are collected as well, e.g.: `flex ${a ? 'b' : 'c'}`.
+fn string(
+    input: &[u8],
+    start: usize,
+    context: &ClassListContext,
+    spans: &mut Vec<ClassListSpan>,
+) -> usize {
+    let quote = input[start];
+    let mut idx = start + 1;
+
+    let end = loop {
+        if idx >= input.len() {
+            break input.len();
+        }
+
+        match input[idx] {
+            b'\\' => idx += 2,
+            b'$' if quote == b'`' && input.get(idx + 1) == Some(&b'{') => {
+                let end = closing(input, idx + 1, b'{', b'}');
+                strings(input, idx + 2..end, context, spans);
+                idx = end + 1;
+            }
+            c if c == quote => break idx,
+            _ => idx += 1,
+        }
+    };
+
+    spans.push(ClassListSpan {
+        range: (start + 1).min(end)..end,
+        quote: Some(quote),
+        context: context.clone(),
+    });
+
+    (end + 1).min(input.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn class_lists(input: &str) -> Vec<&str> {
+        let helpers: Vec<String> = vec!["clsx".into(), "cn".into(), "tw".into()];
+
+        class_list_ranges(input.as_bytes(), &helpers)
+            .into_iter()
+            .map(|range| &input[range])
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn it_should_find_class_attributes() {
+        assert_eq!(
+            class_lists(
+                r#"<div class="flex underline" id="main" data-class="nope">Hello world</div>"#
+            ),
+            vec!["flex underline"]
+        );
+        assert_eq!(
+            class_lists(r#"<div className='flex' classes="nope" tw="mt-4">"#),
+            vec!["flex", "mt-4"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_strings_in_expressions() {
+        assert_eq!(
+            class_lists(r#"<div className={active ? "flex" : 'hidden'}>"#),
+            vec!["flex", "hidden"]
+        );
+        assert_eq!(
+            class_lists(r#"<div :class="{ 'font-bold': active }" v-bind:class="['a', b]">"#),
+            vec!["font-bold", "a"]
+        );
+        assert_eq!(
+            class_lists(r#"<div class:list={["a", { b: true }]}>"#),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_template_literals() {
+        assert_eq!(
+            class_lists(r#"<div className={`flex ${active ? 'font-bold' : ''} p-4`}>"#),
+            vec!["flex ", "font-bold", " p-4"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_helper_calls() {
+        assert_eq!(
+            class_lists(
+                r#"let a = clsx("flex", { "font-bold": b }, c && 'underline'); let d = "nope""#
+            ),
+            vec!["flex", "font-bold", "underline"]
+        );
+        assert_eq!(
+            class_lists("const Button = tw`px-4 py-2`; classNames('nope'); utils.cn('p-4')"),
+            vec!["px-4 py-2", "p-4"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_svelte_class_directives() {
+        assert_eq!(
+            class_lists(r#"<div class:active={isActive} class:font-bold class="p-4">"#),
+            vec!["active", "font-bold", "p-4"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_keyword_arguments() {
+        assert_eq!(
+            class_lists(r#"<%= link_to "Home", root_path, class: "flex underline" %>"#),
+            vec!["flex underline"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_class_list_spans() {
+        let input =
+            r#"<div class="flex p-4" className={clsx('a', `b ${c ? "d" : ''}`)} class:active>"#;
+        let spans: Vec<_> = class_list_spans(input.as_bytes(), &["clsx".into()])
+            .into_iter()
+            .map(|span| {
+                (
+                    &input[span.range],
+                    span.quote.map(char::from),
+                    span.context.kind(),
+                    span.context.name().map(|x| x.to_string()),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("flex p-4", Some('"'), "attribute", Some("class".into())),
+                ("a", Some('\''), "expression", Some("className".into())),
+                (
+                    "b ${c ? \"d\" : ''}",
+                    Some('`'),
+                    "expression",
+                    Some("className".into())
+                ),
+                ("d", Some('"'), "expression", Some("className".into())),
+                ("", Some('\''), "expression", Some("className".into())),
+                ("active", None, "directive", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_find_spans_of_helpers() {
+        let input = "let a = clsx('flex', b && \"p-4\"); const Button = tw`px-4`";
+        let spans: Vec<_> = class_list_spans(input.as_bytes(), &["clsx".into(), "tw".into()])
+            .into_iter()
+            .map(|span| (&input[span.range], span.context.kind()))
+            .collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("flex", "helper"),
+                ("p-4", "helper"),
+                ("px-4", "tagged-template")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_find_spans_in_attribute_expressions() {
+        let input = r#"<div :class="['flex', { 'font-bold': a }]"></div><%= link_to "Home", class: "underline" %>"#;
+        let spans: Vec<_> = class_list_spans(input.as_bytes(), &[])
+            .into_iter()
+            .map(|span| (&input[span.range], span.context))
+            .collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                (
+                    "flex",
+                    ClassListContext::Expression {
+                        name: ":class".into()
+                    }
+                ),
+                (
+                    "font-bold",
+                    ClassListContext::Expression {
+                        name: ":class".into()
+                    }
+                ),
+                (
+                    "underline",
+                    ClassListContext::KeywordArgument {
+                        name: "class".into()
+                    }
+                ),
+            ]
+        );
+    }
+}
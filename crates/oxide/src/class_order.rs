@@ -0,0 +1,216 @@
+use crate::class_list::class_list_ranges;
+use crate::cursor::Cursor;
+use crate::parser::{Extractor, ExtractorOptions};
+use crate::rewrite::TextEdit;
+use crate::scanner::dynamic_classes::{continued_by_expression, continues_expression};
+use fxhash::{FxHashMap, FxHashSet};
+use std::ops::Range;
+
+/// The order of classes as supplied by the compiler, e.g.: the order in which their CSS is
+/// generated.
+#[derive(Debug, Clone, Default)]
+pub struct ClassOrder(FxHashMap<String, u64>);
+
+impl ClassOrder {
+    pub fn new(order: impl IntoIterator<Item = (String, u64)>) -> Self {
+        Self(order.into_iter().collect())
+    }
+
+    /// The sort key of the class, `None` for unknown classes.
+    pub fn get(&self, class: &str) -> Option<u64> {
+        self.0.get(class).copied()
+    }
+}
+
+/// Remove duplicate classes from all class lists, and sort them by `order`, with unknown classes
+/// first. Unknown classes keep their relative order. Returns one edit per changed class list, with
+/// the UTF-16 ranges not computed yet.
+///
+/// Whitespace between the classes stays where it is, e.g.: for class lists that span multiple
+/// lines. Partial classes that continue or are continued by an expression, e.g.: the `bg-` of
+/// `` `bg-${color}` ``, are never moved. Class lists containing anything but candidates, e.g.:
+/// template syntax like `{{ active }}`, are skipped.
+pub fn normalize(input: &[u8], helpers: &[String], order: Option<&ClassOrder>) -> Vec<TextEdit> {
+    class_list_ranges(input, helpers)
+        .into_iter()
+        .filter_map(|range| normalize_class_list(input, range, order))
+        .collect()
+}
+
+fn normalize_class_list(
+    input: &[u8],
+    range: Range<usize>,
+    order: Option<&ClassOrder>,
+) -> Option<TextEdit> {
+    let words = words(input, range.clone());
+    let first = words.first()?.clone();
+    let last = words.last()?.clone();
+
+    // Partial classes stay in place
+    let pinned_start =
+        usize::from(first.start == range.start && continues_expression(input, range.start));
+    let pinned_end =
+        usize::from(last.end == range.end && continued_by_expression(input, range.end));
+    if pinned_start + pinned_end >= words.len() {
+        return None;
+    }
+
+    let classes = words
+        .iter()
+        .map(|word| std::str::from_utf8(&input[word.clone()]).ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let (start, rest) = classes.split_at(pinned_start);
+    let (movable, end) = rest.split_at(rest.len() - pinned_end);
+
+    if !movable.iter().all(|class| is_candidate(class)) {
+        return None;
+    }
+
+    let mut seen = FxHashSet::default();
+    let mut sorted: Vec<_> = movable
+        .iter()
+        .filter(|class| seen.insert(**class))
+        .copied()
+        .collect();
+
+    // Stable, so unknown classes, and classes with the same key keep their order
+    sorted.sort_by_key(|class| order.and_then(|order| order.get(class)));
+
+    let normalized: Vec<_> = start.iter().chain(&sorted).chain(end).collect();
+    if normalized.len() == classes.len() && normalized.iter().zip(&classes).all(|(a, b)| *a == b) {
+        return None;
+    }
+
+    // The separators keep their position, the ones of removed duplicates are dropped
+    let mut replacement = String::new();
+    for (idx, class) in normalized.iter().enumerate() {
+        if idx > 0 {
+            let separator = &input[words[idx - 1].end..words[idx].start];
+            replacement.push_str(std::str::from_utf8(separator).ok()?);
+        }
+        replacement.push_str(class);
+    }
+
+    let range = first.start..last.end;
+    Some(TextEdit {
+        utf16_range: range.clone(),
+        original: std::str::from_utf8(&input[range.clone()]).ok()?.to_string(),
+        range,
+        replacement,
+    })
+}
+
+/// The ranges of all whitespace separated words inside of `range`.
+fn words(input: &[u8], range: Range<usize>) -> Vec<Range<usize>> {
+    let mut words = vec![];
+    let mut cursor = Cursor::new(&input[..range.end]);
+    cursor.move_to(range.start);
+
+    let mut start = None;
+    while cursor.pos < range.end {
+        match (cursor.curr.is_ascii_whitespace(), start) {
+            (false, None) => start = Some(cursor.pos),
+            (true, Some(word)) => {
+                words.push(word..cursor.pos);
+                start = None;
+            }
+            _ => {}
+        }
+
+        cursor.advance_by(1);
+    }
+
+    if let Some(word) = start {
+        words.push(word..range.end);
+    }
+
+    words
+}
+
+/// Whether the whole class is a single candidate.
+fn is_candidate(class: &str) -> bool {
+    Extractor::candidates(class.as_bytes(), ExtractorOptions::default())
+        .any(|candidate| candidate == class.as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rewrite::apply;
+
+    fn normalized(input: &str) -> String {
+        let helpers: Vec<String> = vec!["clsx".into()];
+        let order = ClassOrder::new(
+            ["flex", "p-4", "text-red-500", "hover:underline"]
+                .into_iter()
+                .enumerate()
+                .map(|(idx, class)| (class.to_string(), idx as u64)),
+        );
+
+        let edits = normalize(input.as_bytes(), &helpers, Some(&order));
+        for edit in &edits {
+            assert_eq!(&input[edit.range.clone()], edit.original);
+        }
+
+        apply(input, &edits)
+    }
+
+    #[test]
+    fn it_should_sort_classes() {
+        assert_eq!(
+            normalized(r#"<div class="hover:underline p-4 flex">"#),
+            r#"<div class="flex p-4 hover:underline">"#
+        );
+    }
+
+    #[test]
+    fn it_should_put_unknown_classes_first() {
+        assert_eq!(
+            normalized(r#"<div class="p-4 card flex btn">"#),
+            r#"<div class="card btn flex p-4">"#
+        );
+    }
+
+    #[test]
+    fn it_should_remove_duplicates() {
+        assert_eq!(
+            normalized(r#"<div class=" p-4 flex p-4 card card ">"#),
+            r#"<div class=" card flex p-4 ">"#
+        );
+    }
+
+    #[test]
+    fn it_should_keep_whitespace_in_place() {
+        assert_eq!(
+            normalized("<div class=\"\n  p-4\n  flex\n\">"),
+            "<div class=\"\n  flex\n  p-4\n\">"
+        );
+    }
+
+    #[test]
+    fn it_should_keep_partial_classes_in_place() {
+        assert_eq!(
+            normalized("<div className={`p-4 flex bg-${color}-500 p-4 flex`}>"),
+            "<div className={`flex p-4 bg-${color}-500 flex p-4`}>"
+        );
+        assert_eq!(
+            normalized(r#"clsx("p-4 flex text-" + size)"#),
+            r#"clsx("flex p-4 text-" + size)"#
+        );
+    }
+
+    #[test]
+    fn it_should_skip_class_lists_with_template_syntax() {
+        let input = r#"<div class="p-4 {{ active }} flex">"#;
+        assert_eq!(normalized(input), input);
+    }
+
+    #[test]
+    fn it_should_only_dedupe_without_an_order() {
+        let input = r#"<div class="p-4 flex p-4">"#;
+        let edits = normalize(input.as_bytes(), &[], None);
+
+        assert_eq!(apply(input, &edits), r#"<div class="p-4 flex">"#);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::rewrite::TextEdit;
+use fxhash::FxHashMap;
+use regex::Regex;
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Amount of unchanged lines around the changes in diffs.
+const CONTEXT_LINES: usize = 3;
+
+/// Renames of candidates, e.g.: `shadow` → `shadow-sm`, or `!flex` → `flex!`.
+#[derive(Debug, Clone, Default)]
+pub struct RenameTable {
+    /// Candidates that are renamed as a whole, e.g.: `shadow` → `shadow-sm`
+    pub exact: FxHashMap<String, String>,
+
+    /// Regexes that have to match the whole candidate, together with their replacement, e.g.:
+    /// `^!(.*)$` → `$1!`. Only the first matching pattern is used, exact renames win.
+    pub patterns: Vec<(Regex, String)>,
+}
+
+impl RenameTable {
+    /// The new name of the candidate, if it is renamed.
+    pub fn rename(&self, candidate: &str) -> Option<String> {
+        if let Some(renamed) = self.exact.get(candidate) {
+            return Some(renamed.clone());
+        }
+
+        self.patterns.iter().find_map(|(pattern, replacement)| {
+            let captures = pattern.captures(candidate)?;
+            let whole = captures.get(0)?;
+            if whole.start() != 0 || whole.end() != candidate.len() {
+                return None;
+            }
+
+            let mut renamed = String::new();
+            captures.expand(replacement, &mut renamed);
+            Some(renamed)
+        })
+    }
+}
+
+/// The changes of a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub file: PathBuf,
+
+    /// All applied edits, with ranges in the original content
+    pub edits: Vec<TextEdit>,
+
+    /// Unified diff of the changes, only set in dry-run mode
+    pub diff: Option<String>,
+}
+
+/// The result of a codemod over all files.
+#[derive(Debug, Default)]
+pub struct Codemod {
+    /// Changes of all files that contain renamed candidates, sorted by file
+    pub changes: Vec<FileChange>,
+
+    /// Files that couldn't be read or written, sorted by file
+    pub errors: Vec<(PathBuf, io::Error)>,
+}
+
+/// Write the content to a temporary file next to `file`, and move it into place, so that the file
+/// is never left half-written. The permissions of the file are kept.
+pub fn write_atomically(file: &Path, content: &str) -> io::Result<()> {
+    let name = file
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path is not a file"))?;
+    let temporary = file.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = fs::write(&temporary, content)
+        .and_then(|_| fs::set_permissions(&temporary, fs::metadata(file)?.permissions()))
+        .and_then(|_| fs::rename(&temporary, file));
+
+    if result.is_err() {
+        _ = fs::remove_file(&temporary);
+    }
+
+    result
+}
+
+/// A unified diff of the edits, e.g.: for `git apply`. The `file` is used as is in the headers, so
+/// it should be relative to the project. Edits have to be sorted and must not overlap.
+pub fn unified_diff(file: &Path, content: &str, edits: &[TextEdit]) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    // Byte offset where each line starts
+    let starts: Vec<usize> = lines
+        .iter()
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some(start)
+        })
+        .collect();
+
+    let line_of = |offset: usize| starts.partition_point(|start| *start <= offset) - 1;
+
+    // The edits of each changed line
+    let mut changed: Vec<(usize, Vec<&TextEdit>)> = vec![];
+    for edit in edits {
+        let line = line_of(edit.range.start);
+        match changed.last_mut() {
+            Some((last, edits)) if *last == line => edits.push(edit),
+            _ => changed.push((line, vec![edit])),
+        }
+    }
+
+    let mut diff = format!("--- a/{0}\n+++ b/{0}\n", file.display());
+
+    // Lines that were added or removed before the current hunk
+    let mut delta: isize = 0;
+    let mut idx = 0;
+
+    while idx < changed.len() {
+        // Changes that are close to each other share a hunk
+        let mut end = idx + 1;
+        while end < changed.len() && changed[end].0 - changed[end - 1].0 <= CONTEXT_LINES * 2 + 1 {
+            end += 1;
+        }
+
+        let first = changed[idx].0.saturating_sub(CONTEXT_LINES);
+        let last = (changed[end - 1].0 + CONTEXT_LINES + 1).min(lines.len());
+
+        // Amount of lines of the hunk in the old and new content
+        let mut old = 0;
+        let mut new = 0;
+        let mut body = String::new();
+        let mut removed = vec![];
+        let mut added = vec![];
+        let mut hunk = changed[idx..end].iter().peekable();
+
+        for line in first..last {
+            match hunk.next_if(|(changed, _)| *changed == line) {
+                Some((_, edits)) => {
+                    removed.push(Cow::Borrowed(lines[line]));
+                    added.extend(
+                        apply(lines[line], starts[line], edits)
+                            .split_inclusive('\n')
+                            .map(|line| Cow::Owned(line.to_string())),
+                    );
+                }
+                None => {
+                    flush(&mut body, &mut removed, &mut added, &mut old, &mut new);
+                    push_line(&mut body, ' ', lines[line]);
+                    old += 1;
+                    new += 1;
+                }
+            }
+        }
+        flush(&mut body, &mut removed, &mut added, &mut old, &mut new);
+
+        let old_start = first + 1;
+        let new_start = (first as isize + delta + 1) as usize;
+        delta += new as isize - old as isize;
+
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old, new_start, new
+        ));
+        diff.push_str(&body);
+
+        idx = end;
+    }
+
+    diff
+}
+
+/// Write a run of removed lines followed by the lines that replace them.
+fn flush(
+    body: &mut String,
+    removed: &mut Vec<Cow<str>>,
+    added: &mut Vec<Cow<str>>,
+    old: &mut usize,
+    new: &mut usize,
+) {
+    for line in removed.drain(..) {
+        push_line(body, '-', &line);
+        *old += 1;
+    }
+
+    for line in added.drain(..) {
+        push_line(body, '+', &line);
+        *new += 1;
+    }
+}
+
+fn push_line(body: &mut String, marker: char, line: &str) {
+    body.push(marker);
+    body.push_str(line);
+
+    if !line.ends_with('\n') {
+        body.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// The line with all of its edits applied.
+fn apply(line: &str, start: usize, edits: &[&TextEdit]) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for edit in edits {
+        output.push_str(&line[last..edit.range.start - start]);
+        output.push_str(&edit.replacement);
+        last = edit.range.end - start;
+    }
+
+    output.push_str(&line[last..]);
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rewrite::rewrite;
+
+    fn renames() -> RenameTable {
+        RenameTable {
+            exact: [("shadow".to_string(), "shadow-sm".to_string())]
+                .into_iter()
+                .collect(),
+            patterns: vec![(Regex::new(r"^!(.*)$").unwrap(), "$1!".into())],
+        }
+    }
+
+    fn diff(content: &str) -> String {
+        let words: Vec<_> = content
+            .split(|c: char| c.is_whitespace() || c == '"')
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let start = word.as_ptr() as usize - content.as_ptr() as usize;
+                (word.to_string(), start..start + word.len())
+            })
+            .collect();
+
+        let renames = renames();
+        let rewritten = rewrite(content, words, |candidate| renames.rename(candidate));
+
+        unified_diff(Path::new("index.html"), content, &rewritten.edits)
+    }
+
+    #[test]
+    fn it_should_rename_candidates() {
+        let renames = renames();
+
+        assert_eq!(renames.rename("shadow"), Some("shadow-sm".into()));
+        assert_eq!(renames.rename("!flex"), Some("flex!".into()));
+        assert_eq!(renames.rename("hover:!flex"), None);
+        assert_eq!(renames.rename("flex"), None);
+    }
+
+    #[test]
+    fn it_should_create_unified_diffs() {
+        let content =
+            "a\nb\nc\n<p class=\"shadow !flex\">\nd\ne\nf\ng\nh\ni\nj\nk\n<p class=\"shadow\">";
+
+        assert_eq!(
+            diff(content),
+            [
+                "--- a/index.html",
+                "+++ b/index.html",
+                "@@ -1,7 +1,7 @@",
+                " a",
+                " b",
+                " c",
+                "-<p class=\"shadow !flex\">",
+                "+<p class=\"shadow-sm flex!\">",
+                " d",
+                " e",
+                " f",
+                "@@ -10,4 +10,4 @@",
+                " i",
+                " j",
+                " k",
+                "-<p class=\"shadow\">",
+                "\\ No newline at end of file",
+                "+<p class=\"shadow-sm\">",
+                "\\ No newline at end of file",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_should_merge_close_changes_into_one_hunk() {
+        let content = "shadow\na\nb\nc\nd\ne\nf\n!flex\n";
+
+        assert_eq!(
+            diff(content),
+            [
+                "--- a/index.html",
+                "+++ b/index.html",
+                "@@ -1,8 +1,8 @@",
+                "-shadow",
+                "+shadow-sm",
+                " a",
+                " b",
+                " c",
+                " d",
+                " e",
+                " f",
+                "-!flex",
+                "+flex!",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_should_write_files_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("index.html");
+        fs::write(&file, "<p class=\"shadow\">").unwrap();
+
+        write_atomically(&file, "<p class=\"shadow-sm\">").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "<p class=\"shadow-sm\">"
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::candidate::split_top_level;
+use fxhash::FxHashMap;
+
+/// A compact description of the design system, as known by the compiler. This is used to filter
+/// out candidates that can never result in generated CSS, before they are handed to the compiler.
+#[derive(Debug, Clone, Default)]
+pub struct DesignSystem {
+    /// Roots of functional utilities, e.g.: `bg`, `text`, `translate-x`
+    pub utilities: Vec<String>,
+
+    /// Static utilities, e.g.: `flex`, `underline`
+    pub static_utilities: Vec<String>,
+
+    /// Static, functional and compound variant names, e.g.: `hover`, `data`, `group`
+    pub variants: Vec<String>,
+
+    /// The prefix that every candidate has to start with, e.g.: `tw`
+    pub prefix: Option<String>,
+}
+
+/// A prefix tree over byte strings.
+#[derive(Debug, Clone)]
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: FxHashMap<u8, usize>,
+    terminal: bool,
+}
+
+impl Trie {
+    fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut trie = Self {
+            nodes: vec![TrieNode::default()],
+        };
+
+        for word in words {
+            trie.insert(word.as_bytes());
+        }
+
+        trie
+    }
+
+    fn insert(&mut self, word: &[u8]) {
+        let mut node = 0;
+
+        for c in word {
+            node = match self.nodes[node].children.get(c) {
+                Some(next) => *next,
+                None => {
+                    let next = self.nodes.len();
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[node].children.insert(*c, next);
+                    next
+                }
+            };
+        }
+
+        self.nodes[node].terminal = true;
+    }
+
+    fn contains(&self, word: &[u8]) -> bool {
+        self.walk(word)
+            .last()
+            .is_some_and(|(len, _)| len == word.len())
+    }
+
+    /// Whether a word in the trie is a prefix of `input`, and is followed by one of the `separators`
+    /// (or the end of the input).
+    fn matches_root(&self, input: &[u8], separators: &[u8]) -> bool {
+        self.longest_root(input, separators).is_some()
+    }
+
+    /// The length of the longest word in the trie that matches like in `matches_root`.
+    fn longest_root(&self, input: &[u8], separators: &[u8]) -> Option<usize> {
+        self.walk(input)
+            .map(|(len, _)| len)
+            .filter(|len| *len == input.len() || separators.contains(&input[*len]))
+            .last()
+    }
+
+    /// All lengths of words in the trie that are a prefix of `input`
+    fn walk<'a>(&'a self, input: &'a [u8]) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let mut node = Some(0);
+
+        std::iter::once(0)
+            .chain(input.iter().enumerate().map(|(idx, _)| idx + 1))
+            .map_while(move |len| {
+                let current = node?;
+
+                node = input
+                    .get(len)
+                    .and_then(|c| self.nodes[current].children.get(c).copied());
+
+                Some((len, current))
+            })
+            .filter(|(_, node)| self.nodes[*node].terminal)
+    }
+}
+
+/// A compiled `DesignSystem` that can cheaply check whether candidates are known.
+#[derive(Debug, Clone)]
+pub struct CandidateFilter {
+    utilities: Trie,
+    static_utilities: Trie,
+    variants: Trie,
+    prefix: Option<String>,
+}
+
+impl CandidateFilter {
+    pub fn new(design_system: &DesignSystem) -> Self {
+        Self {
+            utilities: Trie::new(design_system.utilities.iter().map(|x| x.as_str())),
+            static_utilities: Trie::new(design_system.static_utilities.iter().map(|x| x.as_str())),
+            variants: Trie::new(design_system.variants.iter().map(|x| x.as_str())),
+            prefix: design_system.prefix.clone(),
+        }
+    }
+
+    pub(crate) fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// The longest known root of a utility, e.g.: `translate-x` in `translate-x-4`.
+    pub(crate) fn utility_root<'a>(&self, utility: &'a str) -> Option<&'a str> {
+        if self.static_utilities.contains(utility.as_bytes()) {
+            return Some(utility);
+        }
+
+        self.utilities
+            .longest_root(utility.as_bytes(), b"-/")
+            .filter(|len| *len > 0)
+            .map(|len| &utility[..len])
+    }
+
+    /// The longest known root of a variant, e.g.: `group` in `group-hover`.
+    pub(crate) fn variant_root<'a>(&self, variant: &'a str) -> Option<&'a str> {
+        self.variants
+            .longest_root(variant.as_bytes(), b"-/")
+            .filter(|len| *len > 0)
+            .map(|len| &variant[..len])
+    }
+
+    /// Whether the candidate could be a valid candidate in the design system.
+    pub fn matches(&self, candidate: &str) -> bool {
+        let mut segments = split_top_level(candidate, b':');
+
+        // The last segment is the utility, everything before it are variants.
+        let Some(utility) = segments.pop() else {
+            return false;
+        };
+
+        if let Some(prefix) = &self.prefix {
+            if segments.is_empty() || segments.remove(0) != prefix {
+                return false;
+            }
+        }
+
+        segments
+            .iter()
+            .all(|variant| self.matches_variant(variant.as_bytes()))
+            && self.matches_utility(utility.as_bytes())
+    }
+
+    fn matches_variant(&self, variant: &[u8]) -> bool {
+        // Arbitrary variants, e.g.: `[&:hover]`
+        if variant.starts_with(b"[") && variant.ends_with(b"]") {
+            return true;
+        }
+
+        // Static variants, e.g.: `hover`, or functional and compound variants, e.g.: `data-[open]`,
+        // `group-hover`, `group/name`
+        self.variants.matches_root(variant, b"-/")
+    }
+
+    fn matches_utility(&self, utility: &[u8]) -> bool {
+        // Important and negative markers, in either order, e.g.: `!-mx-4`, `-!mx-4`, `mx-4!`
+        let utility = utility.strip_suffix(b"!").unwrap_or(utility);
+        let utility = utility.strip_prefix(b"!").unwrap_or(utility);
+        let utility = utility.strip_prefix(b"-").unwrap_or(utility);
+        let utility = utility.strip_prefix(b"!").unwrap_or(utility);
+
+        // Arbitrary properties, e.g.: `[color:red]`
+        if utility.starts_with(b"[") && utility.ends_with(b"]") {
+            return true;
+        }
+
+        if self.static_utilities.contains(utility) {
+            return true;
+        }
+
+        // Functional utilities, e.g.: `bg-red-500`, `bg-[#0088cc]/50`, or bare roots, e.g.: `border`,
+        // but never without a value, e.g.: `bg-`
+        self.utilities
+            .walk(utility)
+            .any(|(len, _)| match &utility[len..] {
+                [] => true,
+                [b'-' | b'/', value @ ..] => !value.is_empty() && !value.starts_with(b"/"),
+                _ => false,
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn filter(prefix: Option<&str>) -> CandidateFilter {
+        CandidateFilter::new(&DesignSystem {
+            utilities: vec![
+                "bg".into(),
+                "border".into(),
+                "mx".into(),
+                "translate-x".into(),
+            ],
+            static_utilities: vec!["flex".into(), "underline".into()],
+            variants: vec![
+                "hover".into(),
+                "group".into(),
+                "data".into(),
+                "md".into(),
+                "*".into(),
+            ],
+            prefix: prefix.map(Into::into),
+        })
+    }
+
+    #[test]
+    fn it_should_keep_known_utilities() {
+        let filter = filter(None);
+
+        for candidate in [
+            "flex",
+            "underline",
+            "border",
+            "bg-red-500",
+            "bg-[#0088cc]/50",
+            "translate-x-4",
+            "-mx-4",
+            "!-mx-4",
+            "-!mx-4",
+            "mx-4!",
+            "[color:red]",
+        ] {
+            assert!(filter.matches(candidate), "{} should match", candidate);
+        }
+    }
+
+    #[test]
+    fn it_should_drop_unknown_utilities() {
+        let filter = filter(None);
+
+        for candidate in [
+            "the",
+            "return",
+            "className",
+            "flexbox",
+            "bgx-red-500",
+            "translate-y-4",
+            "flex-",
+            "bg-",
+            "-mx-",
+            "bg-/50",
+        ] {
+            assert!(!filter.matches(candidate), "{} should not match", candidate);
+        }
+    }
+
+    #[test]
+    fn it_should_check_all_variants() {
+        let filter = filter(None);
+
+        for candidate in [
+            "hover:flex",
+            "md:hover:bg-red-500",
+            "group-hover:underline",
+            "group/sidebar:underline",
+            "data-[open]:flex",
+            "*:flex",
+            "[&:hover]:flex",
+            "[@media(min-width:200px)]:flex",
+        ] {
+            assert!(filter.matches(candidate), "{} should match", candidate);
+        }
+
+        for candidate in ["focus:flex", "hover:focus:flex", "hovered:flex"] {
+            assert!(!filter.matches(candidate), "{} should not match", candidate);
+        }
+    }
+
+    #[test]
+    fn it_should_require_the_prefix() {
+        let filter = filter(Some("tw"));
+
+        assert!(filter.matches("tw:flex"));
+        assert!(filter.matches("tw:hover:bg-red-500"));
+        assert!(!filter.matches("flex"));
+        assert!(!filter.matches("hover:tw:flex"));
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// A problem found while scanning content, e.g.: an unmatched `tailwind-ignore-start` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The file that contains the problem, not set for in-memory content
+    pub file: Option<PathBuf>,
+
+    /// Byte range of the problem in the original content, or in the transformed content for files
+    /// with a transformer
+    pub range: Range<usize>,
+
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A `tailwind-ignore-start` without a `tailwind-ignore-end`
+    UnmatchedIgnoreStart,
+
+    /// A `tailwind-ignore-end` without a `tailwind-ignore-start`
+    UnmatchedIgnoreEnd,
+
+    /// A `tailwind-ignore-next-line` on the last line
+    MissingNextLine,
+
+    /// A `tailwind-include:` without any candidates
+    EmptyInclude,
+
+    /// A part of a class name that is constructed at runtime, e.g.: the `bg-` of `` `bg-${color}` ``
+    DynamicClass { fragment: String },
+}
+
+impl DiagnosticKind {
+    /// A stable identifier of the kind of problem, e.g.: `unmatched-ignore-start`
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticKind::UnmatchedIgnoreStart => "unmatched-ignore-start",
+            DiagnosticKind::UnmatchedIgnoreEnd => "unmatched-ignore-end",
+            DiagnosticKind::MissingNextLine => "missing-next-line",
+            DiagnosticKind::EmptyInclude => "empty-include",
+            DiagnosticKind::DynamicClass { .. } => "dynamic-class",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::UnmatchedIgnoreStart => {
+                write!(
+                    f,
+                    "`tailwind-ignore-start` is never closed by `tailwind-ignore-end`"
+                )
+            }
+            DiagnosticKind::UnmatchedIgnoreEnd => {
+                write!(
+                    f,
+                    "`tailwind-ignore-end` has no matching `tailwind-ignore-start`"
+                )
+            }
+            DiagnosticKind::MissingNextLine => {
+                write!(f, "`tailwind-ignore-next-line` is not followed by a line")
+            }
+            DiagnosticKind::EmptyInclude => {
+                write!(f, "`tailwind-include:` does not list any candidates")
+            }
+            DiagnosticKind::DynamicClass { fragment } => {
+                write!(
+                    f,
+                    "`{}` is part of a class name that is constructed at runtime and will not be detected, use complete class names instead",
+                    fragment
+                )
+            }
+        }
+    }
+}
@@ -1,21 +1,54 @@
-use crate::parser::Extractor;
+use crate::candidate::{normalize, Candidate};
+use crate::class_list::ClassListSpan;
+use crate::class_order::ClassOrder;
+use crate::codemod::{Codemod, FileChange, RenameTable};
+use crate::design_system::{CandidateFilter, DesignSystem};
+use crate::diagnostics::Diagnostic;
+use crate::parser::{Extractor, ExtractorOptions, StrictMode};
+use crate::rewrite::Rewritten;
+use crate::scanner::candidate_store::CandidateStore;
+use crate::scanner::comments;
+use crate::scanner::custom_extractors::{self, CustomExtractor};
 use crate::scanner::detect_sources::DetectSources;
+use crate::scanner::directives;
+use crate::scanner::dynamic_classes;
+use crate::scanner::heuristics::SkipHeuristics;
+use crate::scanner::html_entities;
+use crate::scanner::offset_map::{self, OffsetMap};
+use crate::scanner::string_literals;
+use crate::scanner::stylesheets;
+use crate::scanner::transformers::{
+    self, TransformCache, TransformInput, Transformer, Transformers,
+};
+use crate::typos::{Typo, TypoIndex};
 use bstr::ByteSlice;
 use fxhash::{FxHashMap, FxHashSet};
 use glob::fast_glob;
 use glob::get_fast_patterns;
 use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::fs;
-use std::path::PathBuf;
-use std::sync;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{self, Arc};
 use std::time::SystemTime;
 use tracing::event;
 
+pub mod candidate;
+pub mod class_list;
+pub mod class_order;
+pub mod codemod;
 pub mod cursor;
+pub mod design_system;
+pub mod diagnostics;
 pub mod fast_skip;
 pub mod glob;
 pub mod parser;
+pub mod rewrite;
 pub mod scanner;
+pub mod typos;
 
 static SHOULD_TRACE: sync::LazyLock<bool> = sync::LazyLock::new(
     || matches!(std::env::var("DEBUG"), Ok(value) if value.eq("*") || value.eq("1") || value.eq("true") || value.contains("tailwind")),
@@ -34,9 +67,139 @@ fn init_tracing() {
 }
 
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ChangedContent {
     pub file: Option<PathBuf>,
     pub content: Option<String>,
+
+    /// Extension of the content, falls back to the extension of the `file`
+    pub extension: Option<String>,
+}
+
+impl ChangedContent {
+    pub fn new(file: Option<PathBuf>, content: Option<String>) -> Self {
+        Self {
+            file,
+            content,
+            extension: None,
+        }
+    }
+
+    /// Set the extension of the content, e.g.: for in-memory content without a `file`.
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = Some(extension.into());
+        self
+    }
+
+    fn extension(&self) -> Option<&str> {
+        extension_or_file(self.extension.as_deref(), self.file.as_deref())
+    }
+}
+
+/// In-memory content that is only borrowed for the duration of the scan.
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedContent<'a> {
+    pub content: &'a [u8],
+
+    /// Extension of the content, falls back to the extension of the `file`
+    pub extension: Option<&'a str>,
+
+    /// File path of the content, if any, e.g.: for diagnostics
+    pub file: Option<&'a Path>,
+}
+
+impl<'a> BorrowedContent<'a> {
+    fn extension(&self) -> Option<&'a str> {
+        extension_or_file(self.extension, self.file)
+    }
+}
+
+/// The `extension`, or the extension of the `file` when it isn't set.
+fn extension_or_file<'a>(extension: Option<&'a str>, file: Option<&'a Path>) -> Option<&'a str> {
+    extension.or_else(|| {
+        file.and_then(|file| file.extension())
+            .and_then(|extension| extension.to_str())
+    })
+}
+
+/// Content to scan, either changed content that we own, or in-memory content that is only
+/// borrowed for the duration of the scan.
+enum Source<'a> {
+    Changed(ChangedContent),
+    Borrowed(BorrowedContent<'a>),
+}
+
+impl Source<'_> {
+    fn extension(&self) -> Option<&str> {
+        match self {
+            Source::Changed(c) => c.extension(),
+            Source::Borrowed(b) => b.extension(),
+        }
+    }
+}
+
+/// Content that is ready for extraction, after all preprocessing steps.
+struct SourceContent<'a> {
+    content: Cow<'a, [u8]>,
+
+    /// Length of the content that was read, before any preprocessing
+    len: usize,
+
+    /// Amount of bytes that were skipped by the `SkipHeuristics`
+    skipped: usize,
+
+    /// Maps offsets in `content` back to offsets in the original content
+    offsets: OffsetMap,
+
+    /// Candidates added with `tailwind-include:` directives, with ranges in the original content
+    included: Vec<(String, Range<usize>)>,
+
+    /// Only extract candidates from class lists
+    strict: bool,
+
+    /// Only extract candidates inside of these ranges, e.g.: the matches of custom extractors
+    ranges: Option<Vec<Range<usize>>>,
+
+    /// Ranges whose words are taken as candidates as-is
+    verbatim: Vec<Range<usize>>,
+
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl SourceContent<'_> {
+    /// All candidates, as slices of `content`
+    fn candidates<'s>(&'s self, options: &'s ScannerOptions) -> impl Iterator<Item = &'s [u8]> {
+        let ranges = match &self.ranges {
+            Some(ranges) => ranges.clone(),
+            None => std::iter::once(0..self.content.len()).collect(),
+        };
+
+        let extracted = ranges.into_iter().flat_map(move |range| {
+            Extractor::candidates(
+                &self.content[range],
+                extractor_options(options, self.strict),
+            )
+        });
+
+        let verbatim = self.verbatim.iter().flat_map(|range| {
+            self.content[range.clone()]
+                .fields_with(char::is_whitespace)
+                .filter(|candidate| std::str::from_utf8(candidate).is_ok())
+        });
+
+        extracted.chain(verbatim)
+    }
+
+    /// All candidates, together with their position in `content`
+    fn candidates_with_positions<'s>(
+        &'s self,
+        options: &'s ScannerOptions,
+    ) -> impl Iterator<Item = (&'s [u8], usize)> {
+        let start = self.content.as_ptr() as usize;
+
+        self.candidates(options)
+            .map(move |candidate| (candidate, candidate.as_ptr() as usize - start))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +223,136 @@ pub struct GlobEntry {
     pub pattern: String,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ScannerOptions {
+    /// Skip regions that can't contain candidates, e.g.: data URIs and minified lines
+    pub skip_heuristics: Option<SkipHeuristics>,
+
+    /// Amount of threads used for scanning. When set, all scanning happens in a dedicated thread
+    /// pool instead of rayon's global pool. A single thread results in sequential, deterministic
+    /// scanning, `0` uses rayon's default amount of threads.
+    pub threads: Option<usize>,
+
+    /// Scan in a dedicated thread pool even if no explicit amount of `threads` is set. The pool
+    /// will use rayon's default amount of threads.
+    pub dedicated_thread_pool: bool,
+
+    /// Only return candidates that are known to the design system. When not set, all candidates
+    /// are returned unfiltered.
+    pub design_system: Option<DesignSystem>,
+
+    /// Track equivalent spellings of candidates by one canonical candidate, e.g.: `!underline` and
+    /// `underline!` both belong to `underline!`. Candidates are still returned as they are spelled
+    /// in the content, see `Scanner::get_spellings` for the mapping.
+    pub normalize_candidates: bool,
+
+    /// Decode HTML entities in attribute values of HTML-like templates before extraction, e.g.:
+    /// `class="[&amp;_p]:mt-2"` contains the `[&_p]:mt-2` candidate.
+    pub decode_html_entities: bool,
+
+    /// Extensions of files where classes live inside of string literals, e.g.: `json` or `ipynb`.
+    /// The escape sequences of these files are unescaped before extraction, e.g.:
+    /// `content-[\"hi\"]` contains the `content-["hi"]` candidate.
+    pub unescape_strings: Vec<String>,
+
+    /// Only extract the candidates of `@apply` rules from stylesheets, e.g.: `.css` or `.scss`
+    /// files. Stylesheets are never detected automatically, they have to be passed in explicitly.
+    ///
+    /// The `<style>` blocks of components, e.g.: `.vue` files, are always handled this way.
+    pub extract_stylesheets: bool,
+
+    /// Extensions of files whose comments are skipped, e.g.: `js` or `html`. In Markdown files,
+    /// fenced code blocks are skipped as well.
+    ///
+    /// Supported are JavaScript and TypeScript, HTML and XML, Vue, Svelte and Astro components,
+    /// Ruby, Python and Elixir, and Markdown and MDX.
+    pub strip_comments: Vec<String>,
+
+    /// Only extract candidates from class-bearing contexts in some files, e.g.: `class="…"`,
+    /// `className={…}` or `clsx(…)`, and ignore all prose and logic.
+    pub strict_extraction: Option<StrictExtraction>,
+
+    /// Regex based extractors for files that the generic extractor can't handle, e.g.: custom
+    /// template languages. Matching files are only extracted with these.
+    pub custom_extractors: Vec<CustomExtractor>,
+
+    /// Report parts of class names that are constructed at runtime as diagnostics, e.g.: the
+    /// `bg-` and `-500` of `` `bg-${color}-500` ``. Only class lists are checked, with the helpers
+    /// of `strict_extraction`, or the default helpers.
+    pub detect_dynamic_classes: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StrictExtraction {
+    /// Extensions of the files that are extracted strictly, e.g.: `html` or `tsx`
+    pub extensions: Vec<String>,
+
+    /// Functions whose string arguments contain classes, e.g.: `clsx` or `cn`
+    pub helpers: Vec<String>,
+}
+
+impl Default for StrictExtraction {
+    fn default() -> Self {
+        Self {
+            extensions: ["html", "htm", "jsx", "tsx", "vue", "svelte", "astro", "erb"]
+                .map(Into::into)
+                .to_vec(),
+            helpers: StrictMode::default().helpers,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Amount of files (or in-memory contents) that were scanned
+    pub files_scanned: usize,
+
+    /// Amount of bytes that were read
+    pub bytes_scanned: usize,
+
+    /// Amount of bytes that were skipped by the `SkipHeuristics`
+    pub bytes_skipped: usize,
+}
+
+impl ScanStats {
+    fn merge(mut self, other: Self) -> Self {
+        self.files_scanned += other.files_scanned;
+        self.bytes_scanned += other.bytes_scanned;
+        self.bytes_skipped += other.bytes_skipped;
+        self
+    }
+}
+
+/// The result of extracting candidates from a batch of content
+#[derive(Debug, Default)]
+struct Extracted {
+    /// Candidates that weren't known before
+    new_candidates: Vec<String>,
+
+    /// Non-canonical spellings, as `(canonical, original)` pairs
+    spellings: Vec<(String, String)>,
+
+    diagnostics: Vec<Diagnostic>,
+
+    stats: ScanStats,
+}
+
+impl Extracted {
+    fn merge(mut self, other: Self) -> Self {
+        self.new_candidates.extend(other.new_candidates);
+        self.spellings.extend(other.spellings);
+        self.diagnostics.extend(other.diagnostics);
+        self.stats = self.stats.merge(other.stats);
+        self
+    }
+}
+
+/// A shared, sorted list of candidates
+type Candidates = Arc<[Arc<str>]>;
+
+/// The transformed content of a file, together with the hash of its original content
+type TransformCacheEntry = (PathBuf, u64, Option<Arc<str>>);
+
 #[derive(Debug, Clone, Default)]
 pub struct Scanner {
     /// Auto content configuration
@@ -68,6 +361,12 @@ pub struct Scanner {
     /// Glob sources
     sources: Option<Vec<GlobEntry>>,
 
+    /// Options to configure how content is scanned
+    options: ScannerOptions,
+
+    /// Dedicated thread pool for scanning, falls back to rayon's global pool when not set
+    thread_pool: Option<Arc<ThreadPool>>,
+
     /// Scanner is ready to scan. We delay the file system traversal for detecting all files until
     /// we actually need them.
     ready: bool,
@@ -82,46 +381,208 @@ pub struct Scanner {
     mtimes: FxHashMap<PathBuf, SystemTime>,
 
     /// Track unique set of candidates
-    candidates: FxHashSet<String>,
+    candidates: CandidateStore,
+
+    /// Statistics about all scanned content so far
+    stats: ScanStats,
+
+    /// Original spellings of normalized candidates, by canonical candidate
+    spellings: FxHashMap<String, BTreeSet<String>>,
+
+    /// Compiled version of the `design_system` option
+    filter: Option<Arc<CandidateFilter>>,
+
+    /// Cached filtered view, together with the unfiltered view it was computed from
+    filtered: Option<(Candidates, Candidates)>,
+
+    /// Candidates that the previous design system rejected, but the current one accepts. Returned
+    /// with the next new candidates, unless a full scan returns them first.
+    accepted: Vec<String>,
+
+    /// Problems found in the most recent scan of each file
+    diagnostics: Vec<Diagnostic>,
+
+    /// Transformers that turn content into text to extract from, by extension
+    transformers: Transformers,
+
+    /// Transformed content of files, by content hash
+    transformed: TransformCache,
+
+    /// Valid classes supplied by the compiler, to suggest fixes for typos
+    typo_index: Option<Arc<TypoIndex>>,
+
+    /// Order of classes supplied by the compiler, to sort class lists
+    class_order: Option<Arc<ClassOrder>>,
 }
 
 impl Scanner {
     pub fn new(detect_sources: Option<DetectSources>, sources: Option<Vec<GlobEntry>>) -> Self {
+        Self::with_options(detect_sources, sources, Default::default())
+    }
+
+    pub fn with_options(
+        detect_sources: Option<DetectSources>,
+        sources: Option<Vec<GlobEntry>>,
+        options: ScannerOptions,
+    ) -> Self {
+        let thread_pool = build_thread_pool(&options).map(Arc::new);
+        let filter = options
+            .design_system
+            .as_ref()
+            .map(|design_system| Arc::new(CandidateFilter::new(design_system)));
+
         Self {
             detect_sources,
             sources,
+            options,
+            thread_pool,
+            filter,
             ..Default::default()
         }
     }
 
+    /// Update the design system that candidates are filtered with, e.g.: when the CSS changed.
+    /// Passing `None` switches to the unfiltered mode.
+    pub fn set_design_system(&mut self, design_system: Option<DesignSystem>) {
+        let previous = std::mem::replace(
+            &mut self.filter,
+            design_system
+                .as_ref()
+                .map(|design_system| Arc::new(CandidateFilter::new(design_system))),
+        );
+        self.filtered = None;
+        self.options.design_system = design_system;
+
+        let filter = self.filter.as_deref();
+        let accepts = |candidate: &str| filter.map_or(true, |filter| filter.matches(candidate));
+        self.accepted.retain(|candidate| accepts(candidate));
+
+        // Seen candidates that were only rejected by the previous design system. They aren't
+        // tracked separately, so that prose doesn't pile up in long-running processes.
+        if let Some(previous) = previous {
+            self.accepted.extend(
+                self.candidates
+                    .sorted()
+                    .iter()
+                    .filter(|candidate| !previous.matches(candidate) && accepts(candidate))
+                    .map(|candidate| candidate.to_string()),
+            );
+        }
+    }
+
+    /// Transform the content of all files with the `extension` before extraction, e.g.: to compile
+    /// MDX with its real compiler. Passing `None` removes the transformer.
+    ///
+    /// The candidate position APIs always use the original content, but diagnostics of transformed
+    /// content refer to the transformed text, because it can't be mapped back.
+    pub fn set_transformer(
+        &mut self,
+        extension: String,
+        transformer: Option<Arc<dyn Transformer>>,
+    ) {
+        self.transformers.set(extension, transformer);
+        self.transformed.clear();
+    }
+
+    pub fn has_transformers(&self) -> bool {
+        !self.transformers.is_empty()
+    }
+
+    /// Run `f` without calling any transformers, e.g.: when they can't be called from the current
+    /// thread. Content with a transformer is scanned as-is instead.
+    pub fn without_transformers<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let transformers = std::mem::take(&mut self.transformers);
+        let result = f(self);
+        self.transformers = transformers;
+        result
+    }
+
     pub fn scan(&mut self) -> Vec<String> {
+        self.scan_shared()
+            .iter()
+            .map(|candidate| candidate.to_string())
+            .collect()
+    }
+
+    /// Same as `scan`, but returns the cached sorted list of candidates instead of a copy. The
+    /// list is only rebuilt when new candidates were found.
+    pub fn scan_shared(&mut self) -> Arc<[Arc<str>]> {
         init_tracing();
         self.prepare();
 
         self.compute_candidates();
 
-        let mut candidates: Vec<String> = self.candidates.clone().into_iter().collect();
+        // The full result already contains all accepted candidates
+        self.accepted.clear();
 
-        candidates.sort();
+        let candidates = self.candidates.sorted();
 
-        candidates
+        let Some(filter) = &self.filter else {
+            return candidates;
+        };
+
+        if let Some((source, filtered)) = &self.filtered {
+            if Arc::ptr_eq(source, &candidates) {
+                return filtered.clone();
+            }
+        }
+
+        let filtered: Arc<[Arc<str>]> = self.install(|| {
+            candidates
+                .par_iter()
+                .filter(|candidate| filter.matches(candidate))
+                .cloned()
+                .collect::<Vec<_>>()
+                .into()
+        });
+
+        self.filtered = Some((candidates, filtered.clone()));
+        filtered
+    }
+
+    /// Same as `scan`, but ignores the design system. This is useful for tools that need to see
+    /// every candidate, e.g.: the upgrader.
+    pub fn scan_unfiltered(&mut self) -> Vec<String> {
+        init_tracing();
+        self.prepare();
+
+        self.compute_candidates();
+
+        self.candidates
+            .sorted()
+            .iter()
+            .map(|candidate| candidate.to_string())
+            .collect()
     }
 
     #[tracing::instrument(skip_all)]
     pub fn scan_content(&mut self, changed_content: Vec<ChangedContent>) -> Vec<String> {
         self.prepare();
-        let candidates = parse_all_blobs(read_all_files(changed_content));
 
-        let mut new_candidates = vec![];
-        for candidate in candidates {
-            if self.candidates.contains(&candidate) {
-                continue;
-            }
-            self.candidates.insert(candidate.clone());
-            new_candidates.push(candidate);
-        }
+        self.extract_candidates(changed_content.into_iter().map(Source::Changed).collect())
+    }
 
-        new_candidates
+    /// Same as `scan_content`, but for in-memory content that is borrowed instead of copied.
+    #[tracing::instrument(skip_all)]
+    pub fn scan_slices(&mut self, contents: &[&[u8]]) -> Vec<String> {
+        self.scan_borrowed(
+            &contents
+                .iter()
+                .map(|content| BorrowedContent {
+                    content,
+                    extension: None,
+                    file: None,
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Same as `scan_slices`, but with the extension of each content.
+    #[tracing::instrument(skip_all)]
+    pub fn scan_borrowed(&mut self, contents: &[BorrowedContent]) -> Vec<String> {
+        self.prepare();
+
+        self.extract_candidates(contents.iter().copied().map(Source::Borrowed).collect())
     }
 
     #[tracing::instrument(skip_all)]
@@ -131,21 +592,262 @@ impl Scanner {
     ) -> Vec<(String, usize)> {
         self.prepare();
 
-        let content = read_changed_content(changed_content).unwrap_or_default();
-        let extractor = Extractor::with_positions(&content[..], Default::default());
+        let Some(source) = read_source(Source::Changed(changed_content), &self.options) else {
+            return vec![];
+        };
 
-        let candidates: Vec<(String, usize)> = extractor
-            .into_iter()
+        let mut candidates: Vec<(String, usize)> = source
+            .candidates_with_positions(&self.options)
             .map(|(s, i)| {
                 // SAFETY: When we parsed the candidates, we already guaranteed that the byte slices
                 // are valid, therefore we don't have to re-check here when we want to convert it back
                 // to a string.
-                unsafe { (String::from_utf8_unchecked(s.to_vec()), i) }
+                unsafe {
+                    (
+                        String::from_utf8_unchecked(s.to_vec()),
+                        source.offsets.to_original(i),
+                    )
+                }
             })
+            .chain(
+                source
+                    .included
+                    .iter()
+                    .map(|(candidate, range)| (candidate.clone(), range.start)),
+            )
             .collect();
+
+        candidates.sort_by_key(|(_, position)| *position);
         candidates
     }
 
+    /// All candidates inside of the borrowed `content`, together with their byte ranges in the
+    /// original content. Candidates can differ from the original content when it was
+    /// preprocessed, e.g.: when HTML entities were decoded.
+    #[tracing::instrument(skip_all)]
+    pub fn get_candidates_with_ranges(
+        &mut self,
+        content: &[u8],
+        extension: Option<&str>,
+    ) -> Vec<(String, Range<usize>)> {
+        self.prepare();
+
+        candidates_with_ranges(content, extension, &self.options)
+    }
+
+    /// The byte ranges of all candidates inside of the borrowed `content`.
+    #[tracing::instrument(skip_all)]
+    pub fn get_candidate_ranges(
+        &mut self,
+        content: &[u8],
+        extension: Option<&str>,
+    ) -> Vec<Range<usize>> {
+        self.get_candidates_with_ranges(content, extension)
+            .into_iter()
+            .map(|(_, range)| range)
+            .collect()
+    }
+
+    /// Replace candidates inside of `content`, e.g.: for codemods. `replace` returns the
+    /// replacement of a candidate, or `None` to keep it. Returns the new content, together with
+    /// all applied edits in UTF-8 and UTF-16 offsets.
+    pub fn rewrite(
+        &mut self,
+        content: &str,
+        extension: Option<&str>,
+        replace: impl FnMut(&str) -> Option<String>,
+    ) -> Rewritten {
+        let candidates = self.get_candidates_with_ranges(content.as_bytes(), extension);
+        rewrite::rewrite(content, candidates, replace)
+    }
+
+    /// Rename candidates in all resolved files in parallel. Only candidates inside of class lists
+    /// are replaced, e.g.: `class="…"` or `clsx(…)`, all other text is kept as-is, even when it
+    /// looks like a candidate, e.g.: `const shadow = …`. In dry-run mode no files are written, and
+    /// every change contains a unified diff instead, with paths relative to the sources.
+    #[tracing::instrument(skip_all)]
+    pub fn codemod(&mut self, renames: &RenameTable, dry_run: bool) -> Codemod {
+        self.prepare();
+
+        let options = &self.options;
+        let bases = self.bases();
+        let results: Vec<_> = self.install(|| {
+            self.files
+                .par_iter()
+                .filter_map(|file| {
+                    let result = fs::read_to_string(file).and_then(|content| {
+                        let extension = file.extension().and_then(|x| x.to_str());
+                        let candidates = class_list_candidates(&content, extension, options);
+                        let rewritten = rewrite::rewrite(&content, candidates, |candidate| {
+                            renames.rename(candidate)
+                        });
+
+                        if rewritten.edits.is_empty() {
+                            return Ok(None);
+                        }
+
+                        let diff = match dry_run {
+                            true => Some(codemod::unified_diff(
+                                relative_to(file, &bases),
+                                &content,
+                                &rewritten.edits,
+                            )),
+                            false => {
+                                codemod::write_atomically(file, &rewritten.content)?;
+                                None
+                            }
+                        };
+
+                        Ok(Some(FileChange {
+                            file: file.clone(),
+                            edits: rewritten.edits,
+                            diff,
+                        }))
+                    });
+
+                    match result {
+                        Ok(change) => change.map(Ok),
+                        Err(error) => Some(Err((file.clone(), error))),
+                    }
+                })
+                .collect()
+        });
+
+        let mut codemod = Codemod::default();
+        for result in results {
+            match result {
+                Ok(change) => codemod.changes.push(change),
+                Err(error) => codemod.errors.push(error),
+            }
+        }
+
+        codemod.changes.sort_by(|a, b| a.file.cmp(&b.file));
+        codemod.errors.sort_by(|a, b| a.0.cmp(&b.0));
+        codemod
+    }
+
+    /// The base directories of the detected sources and of all globs, longest first, e.g.: to
+    /// show paths relative to the project in diffs.
+    fn bases(&self) -> Vec<PathBuf> {
+        let mut bases: Vec<PathBuf> = self
+            .globs
+            .iter()
+            .map(|glob| PathBuf::from(&glob.base))
+            .collect();
+
+        if let Some(detect_sources) = &self.detect_sources {
+            let base = detect_sources.base();
+            bases.extend(dunce::canonicalize(base).ok());
+            bases.push(base.to_path_buf());
+        }
+
+        bases.sort();
+        bases.dedup();
+        bases.sort_by_key(|base| std::cmp::Reverse(base.components().count()));
+        bases
+    }
+
+    /// The full extent of all class lists inside of the borrowed `content`, e.g.: the value of
+    /// `class="…"` or the string arguments of `clsx(…)`. Class lists in ignored regions, and in
+    /// comments when they are stripped, are skipped.
+    pub fn get_class_list_spans(
+        &self,
+        content: &[u8],
+        extension: Option<&str>,
+    ) -> Vec<ClassListSpan> {
+        let content = without_ignored_regions(content, extension, &self.options);
+        class_list::class_list_spans(&content, &class_list_helpers(&self.options))
+    }
+
+    /// Update the order that class lists are sorted by, e.g.: when the CSS changed. Passing `None`
+    /// only removes duplicates.
+    pub fn set_class_order(&mut self, order: Option<Vec<(String, u64)>>) {
+        self.class_order = order.map(|order| Arc::new(ClassOrder::new(order)));
+    }
+
+    /// Remove duplicate classes from all class lists inside of `content`, and sort them by the
+    /// class order, with unknown classes first. Class lists in ignored regions, and in comments
+    /// when they are stripped, are kept as-is.
+    pub fn normalize_class_lists(&self, content: &str, extension: Option<&str>) -> Rewritten {
+        let preprocessed = without_ignored_regions(content.as_bytes(), extension, &self.options);
+        let mut edits = class_order::normalize(
+            &preprocessed,
+            &class_list_helpers(&self.options),
+            self.class_order.as_deref(),
+        );
+
+        rewrite::compute_utf16_ranges(content, &mut edits);
+
+        Rewritten {
+            content: rewrite::apply(content, &edits),
+            edits,
+        }
+    }
+
+    /// Parse a candidate into its structured form, resolving roots with the design system if
+    /// there is one.
+    pub fn parse_candidate<'a>(&self, candidate: &'a str) -> Option<Candidate<'a>> {
+        Candidate::parse_with(candidate, self.filter.as_deref())
+    }
+
+    /// All non-canonical spellings that were normalized, by canonical candidate. Only tracked when
+    /// `normalize_candidates` is enabled.
+    pub fn get_spellings(&self) -> Vec<(String, Vec<String>)> {
+        let mut spellings: Vec<_> = self
+            .spellings
+            .iter()
+            .map(|(canonical, originals)| (canonical.clone(), originals.iter().cloned().collect()))
+            .collect();
+        spellings.sort();
+        spellings
+    }
+
+    /// Update the valid classes that typos are checked against, e.g.: when the CSS changed. Passing
+    /// `None` disables typo checking.
+    pub fn set_valid_classes(&mut self, classes: Option<Vec<String>>) {
+        self.typo_index = classes.map(|classes| Arc::new(TypoIndex::new(classes)));
+    }
+
+    /// All scanned candidates that are close to, but not one of the valid classes, together with
+    /// suggestions, e.g.: `bg-gren-500` → `bg-green-500`.
+    pub fn get_typos(&self) -> Vec<Typo> {
+        let Some(index) = &self.typo_index else {
+            return vec![];
+        };
+
+        let candidates = self.candidates.sorted();
+        self.install(|| {
+            candidates
+                .par_iter()
+                .filter_map(|candidate| index.check(candidate))
+                .collect()
+        })
+    }
+
+    /// Same as `get_typos`, but for the candidates inside of the borrowed `content`, together with
+    /// their byte ranges in the original content.
+    pub fn get_typos_with_ranges(
+        &mut self,
+        content: &[u8],
+        extension: Option<&str>,
+    ) -> Vec<(Typo, Range<usize>)> {
+        let Some(index) = self.typo_index.clone() else {
+            return vec![];
+        };
+
+        self.get_candidates_with_ranges(content, extension)
+            .into_iter()
+            .filter_map(|(candidate, range)| Some((index.check(&candidate)?, range)))
+            .collect()
+    }
+
+    /// All problems found in scanned content, e.g.: unmatched `tailwind-ignore-start` directives.
+    pub fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.sort_by(|a, b| (&a.file, a.range.start).cmp(&(&b.file, b.range.start)));
+        diagnostics
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn get_files(&mut self) -> Vec<String> {
         self.prepare();
@@ -163,6 +865,14 @@ impl Scanner {
         self.globs.clone()
     }
 
+    pub fn get_stats(&self) -> ScanStats {
+        self.stats
+    }
+
+    pub fn get_options(&self) -> &ScannerOptions {
+        &self.options
+    }
+
     #[tracing::instrument(skip_all)]
     fn compute_candidates(&mut self) {
         let mut changed_content = vec![];
@@ -189,13 +899,98 @@ impl Scanner {
                 changed_content.push(ChangedContent {
                     file: Some(path.clone()),
                     content: None,
+                    extension: None,
                 });
             }
         }
 
         if !changed_content.is_empty() {
-            let candidates = parse_all_blobs(read_all_files(changed_content));
-            self.candidates.extend(candidates);
+            self.extract_candidates(changed_content.into_iter().map(Source::Changed).collect());
+        }
+    }
+
+    /// Read and extract all changed content, and track the candidates that we haven't seen
+    /// before. Returns the new candidates in sorted order.
+    fn extract_candidates(&mut self, sources: Vec<Source>) -> Vec<String> {
+        let files: FxHashSet<PathBuf> = sources
+            .iter()
+            .filter_map(|source| match source {
+                Source::Changed(c) => c.file.clone(),
+                Source::Borrowed(b) => b.file.map(Path::to_path_buf),
+            })
+            .collect();
+
+        let (sources, pending): (Vec<_>, Vec<_>) = sources
+            .into_iter()
+            .partition(|source| self.transformers.get(source.extension()).is_none());
+
+        let (extracted, cache) = self.install(|| {
+            if pending.is_empty() {
+                return (
+                    extract_all_content(sources, &self.options, &self.candidates),
+                    vec![],
+                );
+            }
+
+            // Native extraction doesn't have to wait for the transformers
+            let (extracted, (transformed, cache)) = rayon::join(
+                || extract_all_content(sources, &self.options, &self.candidates),
+                || transform_all(pending, &self.transformers, &self.transformed),
+            );
+
+            let transformed = transformed.into_iter().map(Source::Changed).collect();
+            let extracted = extracted.merge(extract_all_content(
+                transformed,
+                &self.options,
+                &self.candidates,
+            ));
+
+            (extracted, cache)
+        });
+
+        for (file, hash, output) in cache {
+            self.transformed.insert(file, hash, output);
+        }
+
+        let Extracted {
+            mut new_candidates,
+            spellings,
+            diagnostics,
+            stats,
+        } = extracted;
+
+        self.stats = self.stats.merge(stats);
+
+        // Diagnostics of rescanned files are replaced
+        self.diagnostics.retain(|diagnostic| {
+            !diagnostic
+                .file
+                .as_ref()
+                .is_some_and(|file| files.contains(file))
+        });
+        self.diagnostics.extend(diagnostics);
+
+        for (canonical, original) in spellings {
+            self.spellings
+                .entry(canonical)
+                .or_default()
+                .insert(original);
+        }
+
+        if let Some(filter) = &self.filter {
+            new_candidates.retain(|candidate| filter.matches(candidate));
+        }
+
+        new_candidates.append(&mut self.accepted);
+        new_candidates.sort();
+        new_candidates
+    }
+
+    /// Run `f` inside the dedicated thread pool (if any), so that all parallel iterators use it.
+    fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) => pool.install(f),
+            None => f(),
         }
     }
 
@@ -215,7 +1010,7 @@ impl Scanner {
     #[tracing::instrument(skip_all)]
     fn detect_sources(&mut self) {
         if let Some(detect_sources) = &self.detect_sources {
-            let (files, globs) = detect_sources.detect();
+            let (files, globs) = self.install(|| detect_sources.detect());
             self.files.extend(files);
             self.globs.extend(globs);
         }
@@ -231,7 +1026,7 @@ impl Scanner {
             return;
         }
 
-        let resolved_files: Vec<_> = match fast_glob(sources) {
+        let resolved_files: Vec<_> = self.install(|| match fast_glob(sources) {
             Ok(matches) => matches
                 .filter_map(|x| dunce::canonicalize(&x).ok())
                 .collect(),
@@ -239,7 +1034,7 @@ impl Scanner {
                 event!(tracing::Level::ERROR, "Failed to resolve glob: {:?}", err);
                 vec![]
             }
-        };
+        });
 
         self.files.extend(resolved_files);
         self.globs.extend(sources.clone());
@@ -274,66 +1069,470 @@ impl Scanner {
     }
 }
 
-fn read_changed_content(c: ChangedContent) -> Option<Vec<u8>> {
-    if let Some(content) = c.content {
-        return Some(content.into_bytes());
+fn build_thread_pool(options: &ScannerOptions) -> Option<ThreadPool> {
+    if options.threads.is_none() && !options.dedicated_thread_pool {
+        return None;
     }
 
-    let Some(file) = c.file else {
-        return Default::default();
+    let mut builder =
+        ThreadPoolBuilder::new().thread_name(|idx| format!("tailwindcss-scanner-{}", idx));
+    // Rayon uses its default amount of threads for `0`
+    if let Some(threads) = options.threads {
+        builder = builder.num_threads(threads);
+    }
+
+    match builder.build() {
+        Ok(pool) => Some(pool),
+        Err(err) => {
+            event!(
+                tracing::Level::ERROR,
+                "Failed to build thread pool, falling back to the global pool: {:?}",
+                err
+            );
+            None
+        }
+    }
+}
+
+/// The content with ignored regions, and comments when they are stripped, blanked out. All
+/// offsets stay the same.
+fn without_ignored_regions<'c>(
+    content: &'c [u8],
+    extension: Option<&str>,
+    options: &ScannerOptions,
+) -> Cow<'c, [u8]> {
+    let mut content = Cow::Borrowed(content);
+
+    let directives = directives::parse(&content);
+    if !directives.is_empty() {
+        directives::apply(content.to_mut(), &directives);
+    }
+
+    if let Some(language) = extension
+        .filter(|x| options.strip_comments.iter().any(|y| y == x))
+        .and_then(comments::Language::from_extension)
+    {
+        comments::strip(content.to_mut(), language);
+    }
+
+    content
+}
+
+/// Same as `candidates_with_ranges`, but only the candidates inside of class lists.
+fn class_list_candidates(
+    content: &str,
+    extension: Option<&str>,
+    options: &ScannerOptions,
+) -> Vec<(String, Range<usize>)> {
+    let preprocessed = without_ignored_regions(content.as_bytes(), extension, options);
+    let class_lists = class_list::class_list_ranges(&preprocessed, &class_list_helpers(options));
+
+    let mut candidates = candidates_with_ranges(content.as_bytes(), extension, options);
+    candidates.retain(|(_, range)| {
+        let idx = class_lists.partition_point(|class_list| class_list.end < range.end);
+        class_lists
+            .get(idx)
+            .is_some_and(|class_list| class_list.start <= range.start)
+    });
+    candidates
+}
+
+/// The `file` relative to the first base that contains it, or the `file` itself when none does.
+fn relative_to<'a>(file: &'a Path, bases: &[PathBuf]) -> &'a Path {
+    bases
+        .iter()
+        .find_map(|base| file.strip_prefix(base).ok())
+        .unwrap_or(file)
+}
+
+/// All candidates inside of `content`, together with their byte ranges in the original content.
+fn candidates_with_ranges(
+    content: &[u8],
+    extension: Option<&str>,
+    options: &ScannerOptions,
+) -> Vec<(String, Range<usize>)> {
+    let source = Source::Borrowed(BorrowedContent {
+        content,
+        extension,
+        file: None,
+    });
+    let Some(source) = read_source(source, options) else {
+        return vec![];
     };
 
-    let Ok(content) = std::fs::read(&file).map_err(|e| {
-        event!(tracing::Level::ERROR, "Failed to read file: {:?}", e);
-        e
-    }) else {
-        return Default::default();
+    let mut candidates: Vec<_> = source
+        .candidates_with_positions(options)
+        .map(|(candidate, start)| {
+            let range = source.offsets.to_original(start)
+                ..source.offsets.to_original(start + candidate.len());
+
+            // SAFETY: When we parsed the candidates, we already guaranteed that the byte slices
+            // are valid, therefore we don't have to re-check here when we want to convert it back
+            // to a string.
+            let candidate = unsafe { std::str::from_utf8_unchecked(candidate) };
+
+            (candidate.to_string(), range)
+        })
+        .chain(source.included.iter().cloned())
+        .collect();
+
+    candidates.sort_by_key(|(_, range)| range.start);
+    candidates
+}
+
+/// Read the content of a source and apply all preprocessing. Borrowed content is only copied when
+/// any of the preprocessing steps are enabled.
+fn read_source<'a>(source: Source<'a>, options: &ScannerOptions) -> Option<SourceContent<'a>> {
+    let (mut content, extension, file) = match source {
+        Source::Changed(c) => {
+            let extension = c.extension().map(|x| Cow::Owned(x.to_string()));
+            let file = c.file.clone();
+            (Cow::Owned(read_raw_content(c)?), extension, file)
+        }
+        Source::Borrowed(b) => (
+            Cow::Borrowed(b.content),
+            b.extension().map(Cow::Borrowed),
+            b.file.map(Path::to_path_buf),
+        ),
     };
 
-    let Some(extension) = file.extension().map(|x| x.to_str()) else {
-        return Some(content);
+    let len = content.len();
+    let extension = extension.as_deref();
+    let mut offsets = OffsetMap::default();
+
+    // Directives run first, so that their ranges are ranges in the original content
+    let directives = directives::parse(&content);
+    let directives::Applied {
+        included,
+        mut diagnostics,
+    } = match directives.is_empty() {
+        true => Default::default(),
+        false => directives::apply(content.to_mut(), &directives),
     };
 
+    if let Some(language) = extension
+        .filter(|x| options.strip_comments.iter().any(|y| y == x))
+        .and_then(comments::Language::from_extension)
+    {
+        comments::strip(content.to_mut(), language);
+    }
+
     match extension {
-        Some("svelte") => Some(content.replace(" class:", " ")),
-        _ => Some(content),
+        Some(x) if options.extract_stylesheets && stylesheets::EXTENSIONS.contains(&x) => {
+            stylesheets::keep_apply_rules(content.to_mut(), stylesheets::Syntax::from_extension(x));
+        }
+        Some(x) if stylesheets::COMPONENT_EXTENSIONS.contains(&x) => {
+            for (range, syntax) in stylesheets::style_blocks(&content) {
+                stylesheets::keep_apply_rules(&mut content.to_mut()[range], syntax);
+            }
+        }
+        _ => {}
+    }
+
+    // Offsets still match the original content, comments and ignored regions are blanked already
+    if options.detect_dynamic_classes {
+        diagnostics.extend(dynamic_classes::find(
+            &content,
+            &class_list_helpers(options),
+        ));
+    }
+
+    for diagnostic in &mut diagnostics {
+        diagnostic.file.clone_from(&file);
+    }
+
+    let strict = options
+        .strict_extraction
+        .as_ref()
+        .is_some_and(|strict| extension.is_some_and(|x| strict.extensions.iter().any(|y| y == x)));
+
+    // Svelte class directives contain a candidate after the `class:`, e.g.: `class:px-4={…}`.
+    // Strict extraction understands them already.
+    if extension == Some("svelte") && !strict {
+        if let Some((replaced, map)) = offset_map::replace_all(&content, b" class:", b" ") {
+            content = Cow::Owned(replaced);
+            offsets.then(map);
+        }
+    }
+
+    if options.decode_html_entities
+        && extension.is_some_and(|x| html_entities::EXTENSIONS.contains(&x))
+    {
+        if let Some((decoded, map)) = html_entities::decode_attribute_values(&content) {
+            content = Cow::Owned(decoded);
+            offsets.then(map);
+        }
     }
+
+    if extension.is_some_and(|x| options.unescape_strings.iter().any(|y| y == x)) {
+        if let Some((unescaped, map)) = string_literals::unescape(&content) {
+            content = Cow::Owned(unescaped);
+            offsets.then(map);
+        }
+    }
+
+    let skipped = match &options.skip_heuristics {
+        Some(heuristics) => heuristics.apply(content.to_mut()),
+        None => 0,
+    };
+
+    let (ranges, verbatim) = custom_ranges(&content, file.as_deref(), extension, options);
+
+    Some(SourceContent {
+        content,
+        len,
+        skipped,
+        offsets,
+        included,
+        diagnostics,
+        strict,
+        ranges,
+        verbatim,
+    })
+}
+
+/// The ranges of all custom extractors that apply to the content, as ranges that have to be
+/// extracted, and ranges whose words are taken as-is.
+fn custom_ranges(
+    content: &[u8],
+    file: Option<&Path>,
+    extension: Option<&str>,
+    options: &ScannerOptions,
+) -> (Option<Vec<Range<usize>>>, Vec<Range<usize>>) {
+    let mut extractors = options
+        .custom_extractors
+        .iter()
+        .filter(|extractor| extractor.applies_to(file, extension))
+        .peekable();
+
+    if extractors.peek().is_none() {
+        return (None, vec![]);
+    }
+
+    let mut ranges = vec![];
+    let mut verbatim = vec![];
+
+    for extractor in extractors {
+        match extractor.verbatim {
+            true => verbatim.extend(extractor.ranges(content)),
+            false => ranges.extend(extractor.ranges(content)),
+        }
+    }
+
+    (
+        Some(custom_extractors::merge_ranges(ranges)),
+        custom_extractors::merge_ranges(verbatim),
+    )
 }
 
+/// Functions whose string arguments contain classes, from `strict_extraction` or the defaults.
+fn class_list_helpers(options: &ScannerOptions) -> Cow<'_, [String]> {
+    match &options.strict_extraction {
+        Some(strict) => Cow::Borrowed(&strict.helpers),
+        None => Cow::Owned(StrictMode::default().helpers),
+    }
+}
+
+fn extractor_options(options: &ScannerOptions, strict: bool) -> ExtractorOptions {
+    ExtractorOptions {
+        strict: options
+            .strict_extraction
+            .as_ref()
+            .filter(|_| strict)
+            .map(|strict| StrictMode {
+                helpers: strict.helpers.clone(),
+            }),
+        ..Default::default()
+    }
+}
+
+/// Transform all sources with the transformer of their extension, in one batched call per
+/// extension. Returns the transformed content, together with the cache entries of transformed
+/// files.
 #[tracing::instrument(skip_all)]
-fn read_all_files(changed_content: Vec<ChangedContent>) -> Vec<Vec<u8>> {
+fn transform_all(
+    sources: Vec<Source>,
+    transformers: &Transformers,
+    cache: &TransformCache,
+) -> (Vec<ChangedContent>, Vec<TransformCacheEntry>) {
     event!(
         tracing::Level::INFO,
-        "Reading {:?} file(s)",
-        changed_content.len()
+        "Transforming {:?} file(s)",
+        sources.len()
     );
 
-    changed_content
+    let mut contents = vec![];
+    let mut pending: FxHashMap<String, Vec<(TransformInput, u64)>> = FxHashMap::default();
+
+    for (extension, input) in sources
         .into_par_iter()
-        .filter_map(read_changed_content)
-        .collect()
-}
+        .filter_map(|source| {
+            let extension = source.extension()?.to_string();
+            let (file, content) = match source {
+                Source::Changed(c) => match c.content {
+                    Some(content) => (c.file, content),
+                    None => {
+                        let file = c.file?;
+                        let content = fs::read(&file)
+                            .map_err(|e| {
+                                event!(tracing::Level::ERROR, "Failed to read file: {:?}", e);
+                            })
+                            .ok()?;
+                        (Some(file), String::from_utf8_lossy(&content).into_owned())
+                    }
+                },
+                Source::Borrowed(b) => (
+                    b.file.map(Path::to_path_buf),
+                    String::from_utf8_lossy(b.content).into_owned(),
+                ),
+            };
 
-#[tracing::instrument(skip_all)]
-fn parse_all_blobs(blobs: Vec<Vec<u8>>) -> Vec<String> {
-    let input: Vec<_> = blobs.iter().map(|blob| &blob[..]).collect();
-    let input = &input[..];
-
-    let mut result: Vec<String> = input
-        .par_iter()
-        .map(|input| Extractor::unique(input, Default::default()))
-        .reduce(Default::default, |mut a, b| {
-            a.extend(b);
-            a
+            Some((extension, TransformInput { file, content }))
         })
-        .into_iter()
-        .map(|s| {
-            // SAFETY: When we parsed the candidates, we already guaranteed that the byte slices
-            // are valid, therefore we don't have to re-check here when we want to convert it back
-            // to a string.
-            unsafe { String::from_utf8_unchecked(s.to_vec()) }
+        .collect::<Vec<_>>()
+    {
+        let hash = transformers::content_hash(input.content.as_bytes());
+        let cached = input.file.as_deref().and_then(|file| cache.get(file, hash));
+
+        match cached {
+            Some(output) => contents.push(ChangedContent {
+                content: Some(output.map_or(input.content, |output| output.to_string())),
+                file: input.file,
+                extension: Some(extension),
+            }),
+            None => pending.entry(extension).or_default().push((input, hash)),
+        }
+    }
+
+    let transformed: Vec<_> = pending
+        .into_par_iter()
+        .flat_map_iter(|(extension, pending)| {
+            let (inputs, hashes): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+            let outputs = match transformers.get(Some(&extension)) {
+                Some(transformer) => transformers::transform_batched(transformer.as_ref(), &inputs),
+                None => vec![None; inputs.len()],
+            };
+
+            inputs
+                .into_iter()
+                .zip(hashes)
+                .zip(outputs)
+                .map(move |((input, hash), output)| (extension.clone(), input, hash, output))
+                .collect::<Vec<_>>()
         })
         .collect();
-    result.sort();
-    result
+
+    let mut entries = vec![];
+    for (extension, input, hash, output) in transformed {
+        let output: Option<Arc<str>> = output.map(Into::into);
+
+        if let Some(file) = &input.file {
+            entries.push((file.clone(), hash, output.clone()));
+        }
+
+        contents.push(ChangedContent {
+            content: Some(output.map_or(input.content, |output| output.to_string())),
+            file: input.file,
+            extension: Some(extension),
+        });
+    }
+
+    (contents, entries)
+}
+
+fn read_raw_content(c: ChangedContent) -> Option<Vec<u8>> {
+    if let Some(content) = c.content {
+        return Some(content.into_bytes());
+    }
+
+    let Some(file) = c.file else {
+        return Default::default();
+    };
+
+    std::fs::read(&file)
+        .map_err(|e| {
+            event!(tracing::Level::ERROR, "Failed to read file: {:?}", e);
+        })
+        .ok()
+}
+
+/// Reads and extracts all changed content in a single parallel pipeline. Each worker reads a
+/// file, extracts its candidates, interns them into the shared `candidates` store and drops the
+/// file contents again. This means that at most one file per thread is kept in memory.
+///
+/// Returns the candidates that were not part of `candidates` yet.
+#[tracing::instrument(skip_all)]
+fn extract_all_content(
+    sources: Vec<Source>,
+    options: &ScannerOptions,
+    candidates: &CandidateStore,
+) -> Extracted {
+    event!(tracing::Level::INFO, "Scanning {:?} file(s)", sources.len());
+
+    let extracted = sources
+        .into_par_iter()
+        .filter_map(|source| read_source(source, options))
+        .map(|source| {
+            let stats = ScanStats {
+                files_scanned: 1,
+                bytes_scanned: source.len,
+                bytes_skipped: source.skipped,
+            };
+
+            let extracted = source
+                .candidates(options)
+                .collect::<FxHashSet<_>>()
+                .into_iter()
+                .map(|candidate| {
+                    // SAFETY: When we parsed the candidates, we already guaranteed that the byte
+                    // slices are valid, therefore we don't have to re-check here when we want to
+                    // convert it back to a string.
+                    unsafe { std::str::from_utf8_unchecked(candidate) }
+                })
+                .chain(
+                    source
+                        .included
+                        .iter()
+                        .map(|(candidate, _)| candidate.as_str()),
+                );
+
+            let extracted: Vec<&str> = extracted.collect();
+
+            // The original spellings are returned, because they are what the markup contains
+            let spellings = match options.normalize_candidates {
+                true => extracted
+                    .iter()
+                    .filter_map(|original| match normalize(original) {
+                        Cow::Owned(canonical) => Some((canonical, original.to_string())),
+                        Cow::Borrowed(_) => None,
+                    })
+                    .collect(),
+                false => vec![],
+            };
+
+            let new_candidates: Vec<String> = candidates
+                .insert_all(extracted.iter().copied())
+                .into_iter()
+                .map(|candidate| candidate.to_string())
+                .collect();
+
+            Extracted {
+                new_candidates,
+                spellings,
+                diagnostics: source.diagnostics,
+                stats,
+            }
+        })
+        .reduce(Extracted::default, Extracted::merge);
+
+    let stats = extracted.stats;
+
+    event!(
+        tracing::Level::INFO,
+        "Skipped {:?} byte(s) of {:?} byte(s)",
+        stats.bytes_skipped,
+        stats.bytes_scanned
+    );
+
+    extracted
 }
@@ -1,4 +1,4 @@
-use crate::{cursor::Cursor, fast_skip::fast_skip};
+use crate::{class_list::class_list_ranges, cursor::Cursor, fast_skip::fast_skip};
 use bstr::ByteSlice;
 use fxhash::FxHashSet;
 use tracing::trace;
@@ -37,6 +37,24 @@ pub enum ValidationResult {
 #[derive(Default)]
 pub struct ExtractorOptions {
     pub preserve_spaces_in_arbitrary: bool,
+
+    /// Only extract candidates from class-bearing contexts, e.g.: `class="…"` or `clsx(…)`,
+    /// instead of from everything that looks like a candidate.
+    pub strict: Option<StrictMode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StrictMode {
+    /// Functions whose string arguments contain classes, e.g.: `clsx("flex", a && "underline")`
+    pub helpers: Vec<String>,
+}
+
+impl Default for StrictMode {
+    fn default() -> Self {
+        Self {
+            helpers: vec!["clsx".into(), "cn".into(), "cva".into(), "tw".into()],
+        }
+    }
 }
 
 pub struct Extractor<'a> {
@@ -62,13 +80,13 @@ pub struct Extractor<'a> {
 
 impl<'a> Extractor<'a> {
     pub fn all(input: &'a [u8], opts: ExtractorOptions) -> Vec<&'a [u8]> {
-        Self::new(input, opts).flatten().collect()
+        Self::candidates(input, opts).collect()
     }
 
     pub fn unique(input: &'a [u8], opts: ExtractorOptions) -> FxHashSet<&'a [u8]> {
         let mut candidates: FxHashSet<&[u8]> = Default::default();
         candidates.reserve(100);
-        candidates.extend(Self::new(input, opts).flatten());
+        candidates.extend(Self::candidates(input, opts));
         candidates
     }
 
@@ -85,7 +103,7 @@ impl<'a> Extractor<'a> {
 
     pub fn with_positions(input: &'a [u8], opts: ExtractorOptions) -> Vec<(&'a [u8], usize)> {
         let mut result = Vec::new();
-        let extractor = Self::new(input, opts).flatten();
+        let extractor = Self::candidates(input, opts);
         for item in extractor {
             // Since the items are slices of the input buffer, we can calculate the start index
             // by doing some pointer arithmetics.
@@ -94,6 +112,25 @@ impl<'a> Extractor<'a> {
         }
         result
     }
+
+    /// All candidates of the input, or only the candidates inside of class lists in strict mode.
+    pub fn candidates(input: &'a [u8], opts: ExtractorOptions) -> impl Iterator<Item = &'a [u8]> {
+        let ranges = match &opts.strict {
+            Some(strict) => class_list_ranges(input, &strict.helpers),
+            None => std::iter::once(0..input.len()).collect(),
+        };
+
+        let preserve_spaces_in_arbitrary = opts.preserve_spaces_in_arbitrary;
+
+        ranges.into_iter().flat_map(move |range| {
+            let opts = ExtractorOptions {
+                preserve_spaces_in_arbitrary,
+                strict: None,
+            };
+
+            Self::new(&input[range], opts).flatten()
+        })
+    }
 }
 
 impl<'a> Extractor<'a> {
@@ -143,6 +180,13 @@ impl<'a> Extractor<'a> {
 
         while !candidate.is_empty() {
             match Extractor::is_valid_candidate_string(candidate) {
+                // Arbitrary values can contain any bytes. Only yield candidates that are valid
+                // UTF-8, so that they can be converted to strings without checking again.
+                ValidationResult::Valid
+                    if !candidate.is_ascii() && std::str::from_utf8(candidate).is_err() =>
+                {
+                    return ParseAction::Skip
+                }
                 ValidationResult::Valid => return ParseAction::SingleCandidate(candidate),
                 ValidationResult::Restart => return ParseAction::RestartAt(self.idx_start + 1),
                 _ => {}
@@ -929,6 +973,7 @@ mod test {
             input.as_bytes(),
             ExtractorOptions {
                 preserve_spaces_in_arbitrary: loose,
+                ..Default::default()
             },
         )
         .into_iter()
@@ -1451,4 +1496,93 @@ mod test {
 
         assert_eq!(candidates, vec![("div", 1), ("class", 5), ("flex", 12),]);
     }
+
+    #[test]
+    fn it_should_keep_unicode_in_arbitrary_values() {
+        for input in [
+            "content-['→']",
+            "before:content-['✓']",
+            "font-['Noto_Sans_日本']",
+            "bg-[url(/日本.png)]",
+            "[&[data-label='東京']]:flex",
+            "group-[[lang=日本]]:underline",
+        ] {
+            assert_eq!(
+                run(&format!(r#"<div class="{}">"#, input), false)[2..],
+                [input]
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_not_split_words_with_non_ascii_letters() {
+        let candidates = run("naïve 日本flex flex日本", false);
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn it_should_only_yield_valid_utf8() {
+        let candidates = Extractor::all(b"content-['\xe2\x86'] bg-[\xff] flex", Default::default());
+        assert_eq!(candidates, vec![b"flex"]);
+
+        // Throw random mixes of candidate characters, multi-byte characters and invalid bytes at
+        // the extractor.
+        let pieces: [&[u8]; 14] = [
+            b"flex",
+            b"-[",
+            b"]",
+            b"'",
+            b":",
+            b" ",
+            b"(",
+            b")",
+            "→".as_bytes(),
+            "日本".as_bytes(),
+            &"日".as_bytes()[..2],
+            b"\xff",
+            b"\xc3",
+            b"!",
+        ];
+
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        for _ in 0..2_000 {
+            let mut input = vec![];
+            for _ in 0..16 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                input.extend_from_slice(pieces[state as usize % pieces.len()]);
+            }
+
+            for candidate in Extractor::all(&input, Default::default()) {
+                assert!(
+                    std::str::from_utf8(candidate).is_ok(),
+                    "{:?} in {:?}",
+                    candidate,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_only_extract_from_class_lists_in_strict_mode() {
+        let input = r#"<p class="flex underline">Hello world, <span className={clsx("font-bold", a && 'italic')}>this is prose</span></p>"#;
+
+        let candidates: Vec<_> = Extractor::with_positions(
+            input.as_bytes(),
+            ExtractorOptions {
+                strict: Some(Default::default()),
+                ..Default::default()
+            },
+        )
+        .into_iter()
+        .map(|(candidate, start)| {
+            assert_eq!(&input.as_bytes()[start..start + candidate.len()], candidate);
+            std::str::from_utf8(candidate).unwrap()
+        })
+        .collect();
+
+        assert_eq!(candidates, vec!["flex", "underline", "font-bold", "italic"]);
+    }
 }
@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+/// A replacement of a candidate in the original content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the replaced candidate
+    pub range: Range<usize>,
+
+    /// Range of the replaced candidate in UTF-16 code units, e.g.: for `String#slice` in
+    /// JavaScript
+    pub utf16_range: Range<usize>,
+
+    pub original: String,
+
+    pub replacement: String,
+}
+
+/// The rewritten content, together with all edits that were applied to the original content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rewritten {
+    pub content: String,
+
+    /// All applied edits, in order, with ranges in the original content
+    pub edits: Vec<TextEdit>,
+}
+
+/// Replace candidates inside of `content`, with `candidates` as found by the scanner, together
+/// with their byte ranges in the original content. `replace` returns the replacement of a
+/// candidate, or `None` to keep it.
+///
+/// When replaced candidates overlap, the first and then the longest one wins. Candidates that are
+/// spelled differently in the original content, e.g.: because HTML entities were decoded, are
+/// kept.
+pub fn rewrite(
+    content: &str,
+    mut candidates: Vec<(String, Range<usize>)>,
+    mut replace: impl FnMut(&str) -> Option<String>,
+) -> Rewritten {
+    candidates.sort_by_key(|(_, range)| (range.start, std::cmp::Reverse(range.end)));
+
+    let mut edits: Vec<TextEdit> = vec![];
+    for (candidate, range) in candidates {
+        if edits
+            .last()
+            .is_some_and(|edit| range.start < edit.range.end)
+        {
+            continue;
+        }
+
+        if content.get(range.clone()) != Some(candidate.as_str()) {
+            continue;
+        }
+
+        let Some(replacement) = replace(&candidate).filter(|x| *x != candidate) else {
+            continue;
+        };
+
+        edits.push(TextEdit {
+            utf16_range: range.clone(),
+            range,
+            original: candidate,
+            replacement,
+        });
+    }
+
+    compute_utf16_ranges(content, &mut edits);
+
+    Rewritten {
+        content: apply(content, &edits),
+        edits,
+    }
+}
+
+/// Convert the byte ranges of all edits to UTF-16 in a single pass. Edits have to be sorted and
+/// must not overlap.
+pub(crate) fn compute_utf16_ranges(content: &str, edits: &mut [TextEdit]) {
+    let mut utf8 = 0;
+    let mut utf16 = 0;
+    for edit in edits {
+        utf16 += utf16_len(&content[utf8..edit.range.start]);
+        edit.utf16_range.start = utf16;
+
+        utf16 += utf16_len(&edit.original);
+        edit.utf16_range.end = utf16;
+
+        utf8 = edit.range.end;
+    }
+}
+
+/// The content with all edits applied. Edits have to be sorted and must not overlap.
+pub(crate) fn apply(content: &str, edits: &[TextEdit]) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for edit in edits {
+        output.push_str(&content[last..edit.range.start]);
+        output.push_str(&edit.replacement);
+        last = edit.range.end;
+    }
+    output.push_str(&content[last..]);
+    output
+}
+
+fn utf16_len(input: &str) -> usize {
+    input.chars().map(char::len_utf16).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidates(content: &str, candidates: &[&str]) -> Vec<(String, Range<usize>)> {
+        candidates
+            .iter()
+            .map(|candidate| {
+                let start = content.find(candidate).unwrap();
+                (candidate.to_string(), start..start + candidate.len())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_should_replace_candidates() {
+        let content = r#"<div class="flex bg-red-500/50 shadow">"#;
+        let rewritten = rewrite(
+            content,
+            candidates(content, &["flex", "bg-red-500/50", "shadow"]),
+            |candidate| match candidate {
+                "bg-red-500/50" => Some("bg-red-500/[0.5]".into()),
+                "shadow" => Some("shadow-sm".into()),
+                _ => None,
+            },
+        );
+
+        assert_eq!(
+            rewritten.content,
+            r#"<div class="flex bg-red-500/[0.5] shadow-sm">"#
+        );
+        assert_eq!(
+            rewritten
+                .edits
+                .iter()
+                .map(|edit| (&content[edit.range.clone()], edit.replacement.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("bg-red-500/50", "bg-red-500/[0.5]"),
+                ("shadow", "shadow-sm")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_compute_utf16_ranges() {
+        let content = "<p class=\"😀 flex\">é shadow</p>";
+        let rewritten = rewrite(
+            content,
+            candidates(content, &["flex", "shadow"]),
+            |candidate| Some(format!("{}-sm", candidate)),
+        );
+
+        let utf16: Vec<u16> = content.encode_utf16().collect();
+        for edit in rewritten.edits {
+            assert_eq!(
+                String::from_utf16(&utf16[edit.utf16_range]).unwrap(),
+                edit.original
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_skip_overlapping_candidates() {
+        let content = "hover:flex";
+        let rewritten = rewrite(
+            content,
+            vec![
+                ("flex".into(), 6..10),
+                ("hover:flex".into(), 0..10),
+                ("hover".into(), 0..5),
+            ],
+            |candidate| Some(candidate.to_uppercase()),
+        );
+
+        assert_eq!(rewritten.content, "HOVER:FLEX");
+        assert_eq!(rewritten.edits.len(), 1);
+    }
+
+    #[test]
+    fn it_should_keep_candidates_that_are_spelled_differently() {
+        // `[&amp;_p]:mt-2` was decoded to `[&_p]:mt-2`
+        let content = "<div class=\"[&amp;_p]:mt-2\">";
+        let rewritten = rewrite(content, vec![("[&_p]:mt-2".into(), 12..26)], |_| {
+            Some("[&_p]:mt-4".into())
+        });
+
+        assert_eq!(rewritten.content, content);
+        assert!(rewritten.edits.is_empty());
+    }
+}
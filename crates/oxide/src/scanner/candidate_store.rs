@@ -0,0 +1,255 @@
+use fxhash::{FxHashMap, FxHasher};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Amount of shards the candidates are spread over. Every shard has its own lock, so workers
+/// inserting candidates at the same time rarely wait on each other.
+const SHARD_COUNT: usize = 16;
+
+/// A stable identifier for an interned candidate. Once a candidate is interned, its id never
+/// changes for the lifetime of the `CandidateStore`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CandidateId(u32);
+
+impl CandidateId {
+    fn new(shard: usize, index: usize) -> Self {
+        Self((index * SHARD_COUNT + shard) as u32)
+    }
+
+    fn shard(&self) -> usize {
+        self.0 as usize % SHARD_COUNT
+    }
+
+    fn index(&self) -> usize {
+        self.0 as usize / SHARD_COUNT
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Shard {
+    /// Lookup from candidate to its id
+    ids: FxHashMap<Arc<str>, CandidateId>,
+
+    /// All candidates in this shard, in insertion order
+    candidates: Vec<Arc<str>>,
+}
+
+/// A concurrent set of interned candidates.
+///
+/// Candidates can be inserted from multiple threads at the same time, and the sorted list of all
+/// candidates is cached until new candidates are inserted.
+#[derive(Debug)]
+pub struct CandidateStore {
+    shards: Vec<Mutex<Shard>>,
+
+    /// Cached sorted view of all candidates, invalidated when new candidates arrive
+    sorted: Mutex<Option<Arc<[Arc<str>]>>>,
+}
+
+impl Default for CandidateStore {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| Default::default()).collect(),
+            sorted: Default::default(),
+        }
+    }
+}
+
+impl Clone for CandidateStore {
+    fn clone(&self) -> Self {
+        Self {
+            shards: self
+                .shards
+                .iter()
+                .map(|shard| Mutex::new(lock(shard).clone()))
+                .collect(),
+            sorted: Mutex::new(lock(&self.sorted).clone()),
+        }
+    }
+}
+
+impl CandidateStore {
+    /// Intern all candidates, and return the ones that were not part of the store yet.
+    ///
+    /// Candidates are grouped per shard first, so that every shard is only locked once.
+    pub fn insert_all<'a>(&self, candidates: impl IntoIterator<Item = &'a str>) -> Vec<Arc<str>> {
+        let mut buckets: Vec<Vec<&str>> = vec![vec![]; SHARD_COUNT];
+        for candidate in candidates {
+            buckets[shard_for(candidate)].push(candidate);
+        }
+
+        let mut new_candidates = vec![];
+
+        for (idx, bucket) in buckets.into_iter().enumerate() {
+            if bucket.is_empty() {
+                continue;
+            }
+
+            let mut shard = lock(&self.shards[idx]);
+            for candidate in bucket {
+                if shard.ids.contains_key(candidate) {
+                    continue;
+                }
+
+                let candidate: Arc<str> = Arc::from(candidate);
+                let id = CandidateId::new(idx, shard.candidates.len());
+                shard.ids.insert(candidate.clone(), id);
+                shard.candidates.push(candidate.clone());
+                new_candidates.push(candidate);
+            }
+        }
+
+        if !new_candidates.is_empty() {
+            *lock(&self.sorted) = None;
+        }
+
+        new_candidates
+    }
+
+    /// Intern a single candidate, and return its id.
+    pub fn insert(&self, candidate: &str) -> CandidateId {
+        self.insert_all([candidate]);
+        self.get_id(candidate)
+            .expect("candidate was inserted right before")
+    }
+
+    pub fn get_id(&self, candidate: &str) -> Option<CandidateId> {
+        lock(&self.shards[shard_for(candidate)])
+            .ids
+            .get(candidate)
+            .copied()
+    }
+
+    pub fn resolve(&self, id: CandidateId) -> Option<Arc<str>> {
+        lock(&self.shards[id.shard()])
+            .candidates
+            .get(id.index())
+            .cloned()
+    }
+
+    pub fn contains(&self, candidate: &str) -> bool {
+        self.get_id(candidate).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| lock(shard).candidates.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All candidates in sorted order. The result is cached until new candidates are inserted.
+    pub fn sorted(&self) -> Arc<[Arc<str>]> {
+        let mut sorted = lock(&self.sorted);
+
+        if let Some(sorted) = &*sorted {
+            return sorted.clone();
+        }
+
+        let mut candidates: Vec<Arc<str>> = self
+            .shards
+            .iter()
+            .flat_map(|shard| lock(shard).candidates.clone())
+            .collect();
+        candidates.sort_unstable();
+
+        let candidates: Arc<[Arc<str>]> = candidates.into();
+        *sorted = Some(candidates.clone());
+        candidates
+    }
+}
+
+#[inline(always)]
+fn shard_for(candidate: &str) -> usize {
+    let mut hasher = FxHasher::default();
+    candidate.hash(&mut hasher);
+    hasher.finish() as usize % SHARD_COUNT
+}
+
+#[inline(always)]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn it_should_only_return_new_candidates() {
+        let store = CandidateStore::default();
+
+        let new_candidates = store.insert_all(["flex", "underline", "flex"]);
+        assert_eq!(new_candidates.len(), 2);
+
+        let new_candidates = store.insert_all(["flex", "font-bold"]);
+        assert_eq!(new_candidates, vec![Arc::from("font-bold")]);
+
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn it_should_assign_stable_ids() {
+        let store = CandidateStore::default();
+
+        let id = store.insert("flex");
+        store.insert_all(
+            (0..100)
+                .map(|n| format!("p-{}", n))
+                .collect::<Vec<_>>()
+                .iter()
+                .map(|x| x.as_str()),
+        );
+
+        assert_eq!(store.insert("flex"), id);
+        assert_eq!(store.get_id("flex"), Some(id));
+        assert_eq!(store.resolve(id), Some(Arc::from("flex")));
+        assert_eq!(store.get_id("underline"), None);
+    }
+
+    #[test]
+    fn it_should_cache_the_sorted_view_until_new_candidates_arrive() {
+        let store = CandidateStore::default();
+        store.insert_all(["underline", "flex"]);
+
+        let a = store.sorted();
+        let b = store.sorted();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(&*a, &[Arc::from("flex"), Arc::from("underline")]);
+
+        // Known candidates don't invalidate the cache
+        store.insert_all(["flex"]);
+        assert!(Arc::ptr_eq(&a, &store.sorted()));
+
+        store.insert_all(["block"]);
+        let c = store.sorted();
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(
+            &*c,
+            &[
+                Arc::from("block"),
+                Arc::from("flex"),
+                Arc::from("underline")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_allow_concurrent_insertions() {
+        let store = CandidateStore::default();
+        let candidates: Vec<String> = (0..1_000).map(|n| format!("w-{}", n % 500)).collect();
+
+        let new_candidates: usize = candidates
+            .par_chunks(10)
+            .map(|chunk| store.insert_all(chunk.iter().map(|x| x.as_str())).len())
+            .sum();
+
+        assert_eq!(new_candidates, 500);
+        assert_eq!(store.len(), 500);
+    }
+}
@@ -0,0 +1,332 @@
+use bstr::ByteSlice;
+use std::ops::Range;
+
+/// The comment syntax of a language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// JavaScript and TypeScript: `//` and `/* */`
+    Js,
+
+    /// HTML and XML: `<!-- -->`
+    Html,
+
+    /// Components that mix markup and scripts, e.g.: Vue: `<!-- -->`, `//` and `/* */`
+    Component,
+
+    /// Ruby, Python and Elixir: `#`
+    Hash,
+
+    /// Markdown: fenced code blocks and `<!-- -->`
+    Markdown,
+
+    /// MDX: fenced code blocks, `<!-- -->` and `{/* */}`
+    Mdx,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "mts" | "cts" => Some(Language::Js),
+            "html" | "htm" | "xhtml" | "xml" => Some(Language::Html),
+            "vue" | "svelte" | "astro" => Some(Language::Component),
+            "rb" | "py" | "ex" | "exs" => Some(Language::Hash),
+            "md" | "markdown" => Some(Language::Markdown),
+            "mdx" => Some(Language::Mdx),
+            _ => None,
+        }
+    }
+
+    fn html_comments(self) -> bool {
+        matches!(
+            self,
+            Language::Html | Language::Component | Language::Markdown | Language::Mdx
+        )
+    }
+
+    fn line_comments(self) -> bool {
+        matches!(self, Language::Js | Language::Component)
+    }
+
+    fn block_comments(self) -> bool {
+        matches!(self, Language::Js | Language::Component | Language::Mdx)
+    }
+
+    fn code_fences(self) -> bool {
+        matches!(self, Language::Markdown | Language::Mdx)
+    }
+
+    /// Whether string literals have to be skipped, so that e.g.: `"https://…"` is not a comment
+    fn strings(self) -> bool {
+        matches!(self, Language::Js | Language::Component | Language::Hash)
+    }
+}
+
+/// Blank out all comments, and fenced code blocks in Markdown, in place. Line breaks are kept, so
+/// that offsets and lines don't change.
+pub fn strip(input: &mut [u8], language: Language) {
+    for range in comment_ranges(input, language) {
+        for c in &mut input[range] {
+            if *c != b'\n' {
+                *c = b' ';
+            }
+        }
+    }
+}
+
+/// The ranges of all comments in a single pass over the input.
+fn comment_ranges(input: &[u8], language: Language) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut idx = 0;
+
+    while idx < input.len() {
+        let at_line_start = idx == 0 || input[idx - 1] == b'\n';
+
+        if language.code_fences() && at_line_start {
+            if let Some(end) = code_fence(input, idx) {
+                ranges.push(idx..end);
+                idx = end;
+                continue;
+            }
+        }
+
+        let rest = &input[idx..];
+        let end = match input[idx] {
+            b'<' if language.html_comments() && rest.starts_with(b"<!--") => {
+                Some(find_end(input, idx + 4, b"-->"))
+            }
+
+            // Line comments, but not URLs, e.g.: `https://…`
+            b'/' if language.line_comments()
+                && rest.starts_with(b"//")
+                && (idx == 0 || input[idx - 1] != b':') =>
+            {
+                Some(line_end(input, idx))
+            }
+
+            b'/' if language.block_comments() && rest.starts_with(b"/*") => {
+                Some(find_end(input, idx + 2, b"*/"))
+            }
+
+            b'#' if language == Language::Hash
+                && (idx == 0 || input[idx - 1].is_ascii_whitespace()) =>
+            {
+                Some(line_end(input, idx))
+            }
+
+            b'"' | b'\'' | b'`' if language.strings() => {
+                idx = string_end(input, idx);
+                continue;
+            }
+
+            _ => None,
+        };
+
+        match end {
+            Some(end) => {
+                ranges.push(idx..end);
+                idx = end;
+            }
+            None => idx += 1,
+        }
+    }
+
+    ranges
+}
+
+/// The end of the first `closer` after `start`, or the end of the input when it's not closed.
+fn find_end(input: &[u8], start: usize, closer: &[u8]) -> usize {
+    input[start..]
+        .find(closer)
+        .map_or(input.len(), |idx| start + idx + closer.len())
+}
+
+/// The position of the line break that ends the line containing `start`.
+fn line_end(input: &[u8], start: usize) -> usize {
+    input[start..]
+        .find_byte(b'\n')
+        .map_or(input.len(), |idx| start + idx)
+}
+
+/// The end of the string literal that starts at `start`. Single and double quoted strings end at
+/// the end of the line, template literals and triple quoted strings can span multiple lines.
+fn string_end(input: &[u8], start: usize) -> usize {
+    let quote = input[start];
+
+    if quote != b'`' && input[start..].starts_with(&[quote; 3]) {
+        return find_end(input, start + 3, &[quote; 3]);
+    }
+
+    let mut idx = start + 1;
+    while idx < input.len() {
+        match input[idx] {
+            b'\\' => idx += 2,
+            b'\n' if quote != b'`' => return idx,
+            c if c == quote => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+
+    input.len()
+}
+
+/// The end of the fenced code block that starts at `start`, e.g.: ` ```html `. Unclosed code
+/// blocks continue until the end of the input.
+fn code_fence(input: &[u8], start: usize) -> Option<usize> {
+    let (fence, len) = fence_at(input, start)?;
+    let mut idx = line_end(input, start);
+
+    while idx < input.len() {
+        let line = idx + 1;
+        idx = line_end(input, line);
+
+        if let Some((closing, closing_len)) = fence_at(input, line) {
+            let info = &input[line..idx].trim_start()[closing_len..];
+            if closing == fence && closing_len >= len && info.trim().is_empty() {
+                return Some(idx);
+            }
+        }
+    }
+
+    Some(input.len())
+}
+
+/// The fence character and its length when a line starts with a code fence, indented by at most 3
+/// spaces, e.g.: ` ``` ` or `~~~~`.
+fn fence_at(input: &[u8], start: usize) -> Option<(u8, usize)> {
+    let indent = input[start..]
+        .iter()
+        .take(4)
+        .take_while(|c| **c == b' ')
+        .count();
+    if indent > 3 {
+        return None;
+    }
+
+    let fence = *input.get(start + indent)?;
+    if fence != b'`' && fence != b'~' {
+        return None;
+    }
+
+    let len = input[start + indent..]
+        .iter()
+        .take_while(|c| **c == fence)
+        .count();
+
+    (len >= 3).then_some((fence, len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(input: &str, language: Language) -> Vec<String> {
+        let mut output = input.as_bytes().to_vec();
+        strip(&mut output, language);
+
+        assert_eq!(output.len(), input.len());
+        assert_eq!(output.lines().count(), input.as_bytes().lines().count());
+
+        String::from_utf8(output)
+            .unwrap()
+            .split_whitespace()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn it_should_strip_js_comments() {
+        assert_eq!(
+            words(
+                "let a = 'flex' // underline\n/* <div className=\"hidden\" /> */\nlet b = \"https://a.com\"",
+                Language::Js
+            ),
+            vec!["let", "a", "=", "'flex'", "let", "b", "=", "\"https://a.com\""]
+        );
+
+        // Comment markers inside of strings are kept
+        assert_eq!(
+            words("let a = `// ${b} /* c */`", Language::Js),
+            vec!["let", "a", "=", "`//", "${b}", "/*", "c", "*/`"]
+        );
+    }
+
+    #[test]
+    fn it_should_strip_html_comments() {
+        assert_eq!(
+            words(
+                "<div class=\"flex\">\n<!--\n<p class=\"hidden\"></p>\n-->\n</div>",
+                Language::Html
+            ),
+            vec!["<div", "class=\"flex\">", "</div>"]
+        );
+    }
+
+    #[test]
+    fn it_should_strip_hash_comments() {
+        assert_eq!(
+            words(
+                "# A comment\nclass_name = \"flex #{a}\" # underline\nbg = 'bg-[#fff]'",
+                Language::Hash
+            ),
+            vec![
+                "class_name",
+                "=",
+                "\"flex",
+                "#{a}\"",
+                "bg",
+                "=",
+                "'bg-[#fff]'"
+            ]
+        );
+
+        assert_eq!(
+            words("\"\"\"\n# Not a comment\n\"\"\" # comment", Language::Hash),
+            vec!["\"\"\"", "#", "Not", "a", "comment", "\"\"\""]
+        );
+    }
+
+    #[test]
+    fn it_should_strip_fenced_code_blocks() {
+        assert_eq!(
+            words(
+                "# Title\n\n```html\n<div class=\"flex\"></div>\n```\n\nSome text\n\n~~~~\n~~~\n<p></p>\n~~~~\n<!-- hidden -->\nend",
+                Language::Markdown
+            ),
+            vec!["#", "Title", "Some", "text", "end"]
+        );
+
+        // Unclosed code blocks continue until the end
+        assert_eq!(
+            words("text\n   ```\ncode", Language::Markdown),
+            vec!["text"]
+        );
+
+        // Indented by 4 spaces is not a fence
+        assert_eq!(
+            words("    ```\ncode", Language::Markdown),
+            vec!["```", "code"]
+        );
+    }
+
+    #[test]
+    fn it_should_strip_comments_in_components() {
+        assert_eq!(
+            words(
+                "<template>\n<!-- <p class=\"a\"></p> -->\n</template>\n<script>\n// b\nlet c = 'd' /* e */\n</script>",
+                Language::Component
+            ),
+            vec!["<template>", "</template>", "<script>", "let", "c", "=", "'d'", "</script>"]
+        );
+    }
+
+    #[test]
+    fn it_should_strip_mdx_comments() {
+        assert_eq!(
+            words(
+                "<div className=\"flex\">{/* <p className=\"hidden\" /> */}</div>\n// not a comment",
+                Language::Mdx
+            ),
+            vec!["<div", "className=\"flex\">{", "}</div>", "//", "not", "a", "comment"]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use glob_match::glob_match;
+use regex::bytes::Regex;
+use std::ops::Range;
+use std::path::Path;
+
+/// Extracts candidates from the matches of regexes, for files that the generic `Extractor` can't
+/// handle, e.g.: `@classes(flex underline)` in a custom template language.
+#[derive(Debug, Clone, Default)]
+pub struct CustomExtractor {
+    /// Extensions of the files this extractor applies to, e.g.: `dsl`
+    pub extensions: Vec<String>,
+
+    /// Globs of the files this extractor applies to, matched against the full path, e.g.:
+    /// `**/*.ui.yaml`
+    pub globs: Vec<String>,
+
+    /// Regexes whose capture groups contain the candidates, e.g.: `@classes\(([^)]*)\)`. Regexes
+    /// without capture groups use the whole match.
+    pub patterns: Vec<Regex>,
+
+    /// Take the whitespace separated words of the matches as candidates as-is, instead of
+    /// running the matches through the `Extractor`.
+    pub verbatim: bool,
+}
+
+impl CustomExtractor {
+    pub fn applies_to(&self, file: Option<&Path>, extension: Option<&str>) -> bool {
+        if extension.is_some_and(|x| self.extensions.iter().any(|y| y == x)) {
+            return true;
+        }
+
+        let Some(file) = file.and_then(|file| file.to_str()) else {
+            return false;
+        };
+
+        self.globs.iter().any(|glob| glob_match(glob, file))
+    }
+
+    /// The ranges of all captures of all patterns, sorted and without overlaps.
+    pub fn ranges(&self, input: &[u8]) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+
+        for pattern in &self.patterns {
+            for captures in pattern.captures_iter(input) {
+                match captures.len() {
+                    1 => ranges.extend(captures.get(0).map(|m| m.range())),
+                    _ => ranges.extend(captures.iter().skip(1).flatten().map(|m| m.range())),
+                }
+            }
+        }
+
+        merge_ranges(ranges)
+    }
+}
+
+/// Sort the ranges and merge the ones that overlap.
+pub fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn extractor(patterns: &[&str]) -> CustomExtractor {
+        CustomExtractor {
+            extensions: vec!["dsl".into()],
+            globs: vec!["**/*.ui.yaml".into()],
+            patterns: patterns.iter().map(|x| Regex::new(x).unwrap()).collect(),
+            verbatim: false,
+        }
+    }
+
+    fn matches<'a>(extractor: &CustomExtractor, input: &'a str) -> Vec<&'a str> {
+        extractor
+            .ranges(input.as_bytes())
+            .into_iter()
+            .map(|range| &input[range])
+            .collect()
+    }
+
+    #[test]
+    fn it_should_apply_to_extensions_and_globs() {
+        let extractor = extractor(&[]);
+
+        assert!(extractor.applies_to(None, Some("dsl")));
+        assert!(extractor.applies_to(Some(Path::new("/app/src/button.ui.yaml")), Some("yaml")));
+        assert!(!extractor.applies_to(Some(Path::new("/app/src/config.yaml")), Some("yaml")));
+        assert!(!extractor.applies_to(None, None));
+    }
+
+    #[test]
+    fn it_should_use_capture_groups() {
+        let extractor = extractor(&[r"@classes\(([^)]*)\)", r"classes: \[([^\]]*)\]"]);
+
+        assert_eq!(
+            matches(
+                &extractor,
+                "@classes(flex underline) text @classes(p-4)\nclasses: [mt-2, mb-2]"
+            ),
+            vec!["flex underline", "p-4", "mt-2, mb-2"]
+        );
+    }
+
+    #[test]
+    fn it_should_use_the_whole_match_without_capture_groups() {
+        let extractor = extractor(&[r"tw-[a-z0-9-]+"]);
+
+        assert_eq!(
+            matches(&extractor, "a tw-flex b tw-p-4"),
+            vec!["tw-flex", "tw-p-4"]
+        );
+    }
+
+    #[test]
+    fn it_should_merge_overlapping_matches() {
+        let extractor = extractor(&[r"\[([^\]]*)\]", r"\[(a b)"]);
+
+        assert_eq!(matches(&extractor, "[a b c] [d]"), vec!["a b c", "d"]);
+    }
+}
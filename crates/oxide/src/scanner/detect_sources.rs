@@ -2,7 +2,7 @@ use crate::scanner::allowed_paths::{is_allowed_content_path, resolve_allowed_pat
 use crate::GlobEntry;
 use fxhash::FxHashSet;
 use std::cmp::Ordering;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync;
 use walkdir::WalkDir;
 
@@ -27,6 +27,10 @@ impl DetectSources {
         Self { base }
     }
 
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
     pub fn detect(&self) -> (Vec<PathBuf>, Vec<GlobEntry>) {
         let (files, dirs) = self.resolve_files();
         let globs = self.resolve_globs(&dirs);
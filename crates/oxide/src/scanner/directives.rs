@@ -0,0 +1,337 @@
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use bstr::ByteSlice;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveKind {
+    /// `tailwind-ignore-start`, ignores everything up to the next `tailwind-ignore-end`
+    IgnoreStart,
+
+    /// `tailwind-ignore-end`
+    IgnoreEnd,
+
+    /// `tailwind-ignore-next-line`, ignores the line after the comment
+    IgnoreNextLine,
+
+    /// `tailwind-include: a b c`, adds all listed candidates
+    Include,
+}
+
+/// A directive inside of a comment, e.g.: `<!-- tailwind-ignore-start -->`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    pub kind: DirectiveKind,
+
+    /// The whole comment that contains the directive
+    pub comment: Range<usize>,
+
+    /// Everything after the directive until the end of the comment
+    pub params: Range<usize>,
+}
+
+const DIRECTIVES: &[(&[u8], DirectiveKind)] = &[
+    (b"tailwind-ignore-start", DirectiveKind::IgnoreStart),
+    (b"tailwind-ignore-end", DirectiveKind::IgnoreEnd),
+    (b"tailwind-ignore-next-line", DirectiveKind::IgnoreNextLine),
+    (b"tailwind-include:", DirectiveKind::Include),
+];
+
+/// Comment openers with their closers, line comments don't have a closer. Longer openers come
+/// first, e.g.: `{{!--` before `{{!`.
+const COMMENTS: &[(&[u8], Option<&[u8]>)] = &[
+    (b"{{!--", Some(b"--}}")),
+    (b"<!--", Some(b"-->")),
+    (b"{{!", Some(b"}}")),
+    (b"/*", Some(b"*/")),
+    (b"//", None),
+    (b"#", None),
+];
+
+/// Find all directives. A directive has to be the first thing in its comment, e.g.: `// foo
+/// tailwind-ignore-next-line` is not a directive.
+pub fn parse(input: &[u8]) -> Vec<Directive> {
+    let mut directives = vec![];
+    let mut searched_until = 0;
+
+    for pos in input.find_iter(b"tailwind-") {
+        if pos < searched_until {
+            continue;
+        }
+
+        let Some((keyword, kind)) = DIRECTIVES
+            .iter()
+            .find(|(keyword, _)| input[pos..].starts_with(keyword))
+        else {
+            continue;
+        };
+
+        let Some((start, closer)) = comment_opener(input, pos) else {
+            continue;
+        };
+
+        let after = pos + keyword.len();
+        let (params_end, end) = match closer {
+            Some(closer) => match input[after..].find(closer) {
+                Some(idx) => (after + idx, after + idx + closer.len()),
+                None => continue,
+            },
+            None => {
+                let end = input[after..]
+                    .find_byte(b'\n')
+                    .map_or(input.len(), |idx| after + idx);
+                (end, end)
+            }
+        };
+
+        // The keyword has to end, e.g.: `tailwind-ignore-start-here` is not a directive
+        if *kind != DirectiveKind::Include
+            && after < params_end
+            && !input[after].is_ascii_whitespace()
+        {
+            continue;
+        }
+
+        directives.push(Directive {
+            kind: *kind,
+            comment: start..end,
+            params: after..params_end,
+        });
+        searched_until = end;
+    }
+
+    directives
+}
+
+/// The start of the comment that directly precedes the directive at `pos`, together with the
+/// closer of the comment.
+fn comment_opener(input: &[u8], pos: usize) -> Option<(usize, Option<&'static [u8]>)> {
+    let line_start = input[..pos].rfind_byte(b'\n').map_or(0, |idx| idx + 1);
+    let before = input[line_start..pos].trim_end();
+
+    let (opener, closer) = COMMENTS
+        .iter()
+        .find(|(opener, _)| before.ends_with(opener))?;
+
+    let start = line_start + before.len() - opener.len();
+
+    // Line comments have to be separated from the code before them, e.g.: not `https://`
+    if closer.is_none() && start > 0 && !input[start - 1].is_ascii_whitespace() {
+        return None;
+    }
+
+    Some((start, *closer))
+}
+
+/// The result of applying all directives.
+#[derive(Debug, Default)]
+pub struct Applied {
+    /// Candidates that were added with `tailwind-include:`, together with their ranges
+    pub included: Vec<(String, Range<usize>)>,
+
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Blank out all ignored regions and the directive comments themselves, in place.
+pub fn apply(input: &mut [u8], directives: &[Directive]) -> Applied {
+    let mut included = vec![];
+    let mut diagnostics = vec![];
+    let mut ignored: Vec<Range<usize>> = vec![];
+    let mut open: Vec<&Directive> = vec![];
+
+    let mut report = |directive: &Directive, kind| {
+        diagnostics.push(Diagnostic {
+            file: None,
+            range: directive.comment.clone(),
+            kind,
+        });
+    };
+
+    for directive in directives {
+        match directive.kind {
+            DirectiveKind::IgnoreStart => open.push(directive),
+
+            DirectiveKind::IgnoreEnd => match open.pop() {
+                // Only the outermost pair matters when they are nested
+                Some(start) if open.is_empty() => {
+                    ignored.push(start.comment.start..directive.comment.end)
+                }
+                Some(_) => {}
+                None => report(directive, DiagnosticKind::UnmatchedIgnoreEnd),
+            },
+
+            DirectiveKind::IgnoreNextLine => {
+                match input[directive.comment.end..].find_byte(b'\n') {
+                    Some(idx) => {
+                        let start = directive.comment.end + idx + 1;
+                        let end = input[start..]
+                            .find_byte(b'\n')
+                            .map_or(input.len(), |idx| start + idx);
+                        ignored.push(start..end);
+                    }
+                    None => report(directive, DiagnosticKind::MissingNextLine),
+                }
+            }
+
+            DirectiveKind::Include => {
+                let params = &input[directive.params.clone()];
+                let candidates: Vec<_> = params
+                    .fields_with(|c| c.is_whitespace())
+                    .filter_map(|field| {
+                        let start = directive.params.start
+                            + (field.as_ptr() as usize - params.as_ptr() as usize);
+                        let candidate = std::str::from_utf8(field).ok()?;
+                        Some((candidate.to_string(), start..start + field.len()))
+                    })
+                    .collect();
+
+                if candidates.is_empty() {
+                    report(directive, DiagnosticKind::EmptyInclude);
+                }
+
+                included.extend(candidates);
+            }
+        }
+    }
+
+    for directive in open {
+        report(directive, DiagnosticKind::UnmatchedIgnoreStart);
+    }
+
+    // Includes inside of ignored regions are ignored as well
+    included.retain(|(_, range)| !ignored.iter().any(|ignored| ignored.contains(&range.start)));
+
+    for range in ignored
+        .iter()
+        .chain(directives.iter().map(|directive| &directive.comment))
+    {
+        for c in &mut input[range.clone()] {
+            if *c != b'\n' {
+                *c = b' ';
+            }
+        }
+    }
+
+    Applied {
+        included,
+        diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(input: &str) -> (String, Applied) {
+        let mut output = input.as_bytes().to_vec();
+        let applied = apply(&mut output, &parse(input.as_bytes()));
+
+        assert_eq!(output.len(), input.len());
+        (String::from_utf8(output).unwrap(), applied)
+    }
+
+    fn words(input: &str) -> Vec<&str> {
+        input.split_whitespace().collect()
+    }
+
+    #[test]
+    fn it_should_ignore_regions() {
+        let (output, applied) = run(
+            "flex\n<!-- tailwind-ignore-start -->\n<p>legal text</p>\n<!-- tailwind-ignore-end -->\nunderline",
+        );
+
+        assert_eq!(words(&output), vec!["flex", "underline"]);
+        assert!(applied.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn it_should_ignore_the_next_line() {
+        let (output, _) = run("flex\n// tailwind-ignore-next-line\nlet foo = 'bar'\nunderline");
+        assert_eq!(words(&output), vec!["flex", "underline"]);
+
+        let (output, _) = run("<div>{/* tailwind-ignore-next-line */}\nfoo\n</div>");
+        assert_eq!(words(&output), vec!["<div>{", "}", "</div>"]);
+    }
+
+    #[test]
+    fn it_should_support_common_comment_syntaxes() {
+        for input in [
+            "<!-- tailwind-include: a b -->",
+            "// tailwind-include: a b",
+            "/* tailwind-include: a b */",
+            "# tailwind-include: a b",
+            "{{!-- tailwind-include: a b --}}",
+        ] {
+            let (output, applied) = run(input);
+
+            assert_eq!(words(&output), Vec::<&str>::new(), "{}", input);
+            assert_eq!(
+                applied
+                    .included
+                    .iter()
+                    .map(|(candidate, range)| (candidate.as_str(), &input[range.clone()]))
+                    .collect::<Vec<_>>(),
+                vec![("a", "a"), ("b", "b")],
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_not_match_directives_outside_of_comments() {
+        for input in [
+            "tailwind-ignore-start",
+            "let x = 'tailwind-ignore-next-line'",
+            "https://tailwind-include: a",
+            "// tailwind-ignore-start-here",
+        ] {
+            assert_eq!(parse(input.as_bytes()), vec![], "{}", input);
+        }
+    }
+
+    #[test]
+    fn it_should_handle_nested_regions() {
+        let (output, applied) = run(
+            "# tailwind-ignore-start\na\n# tailwind-ignore-start\nb\n# tailwind-ignore-end\nc\n# tailwind-ignore-end\nd",
+        );
+
+        assert_eq!(words(&output), vec!["d"]);
+        assert!(applied.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn it_should_report_unmatched_directives() {
+        let input = "// tailwind-ignore-end\nflex\n// tailwind-include:\n// tailwind-ignore-start\nunderline\n// tailwind-ignore-next-line";
+        let (output, applied) = run(input);
+
+        // Unmatched regions are not ignored
+        assert_eq!(words(&output), vec!["flex", "underline"]);
+        assert_eq!(
+            applied
+                .diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.kind.clone(), &input[diagnostic.range.clone()]))
+                .collect::<Vec<_>>(),
+            vec![
+                (DiagnosticKind::UnmatchedIgnoreEnd, "// tailwind-ignore-end"),
+                (DiagnosticKind::EmptyInclude, "// tailwind-include:"),
+                (
+                    DiagnosticKind::MissingNextLine,
+                    "// tailwind-ignore-next-line"
+                ),
+                (
+                    DiagnosticKind::UnmatchedIgnoreStart,
+                    "// tailwind-ignore-start"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_drop_includes_in_ignored_regions() {
+        let (_, applied) = run(
+            "/* tailwind-ignore-start */\n/* tailwind-include: flex */\n/* tailwind-ignore-end */",
+        );
+        assert!(applied.included.is_empty());
+    }
+}
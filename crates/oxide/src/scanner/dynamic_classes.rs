@@ -0,0 +1,151 @@
+use crate::class_list::class_list_ranges;
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use std::ops::Range;
+
+/// Find parts of class names that are constructed at runtime, e.g.: the `bg-` and `-500` of
+/// `` `bg-${color}-500` ``, or the `text-` of `"text-" + size`. These never produce candidates.
+///
+/// Only class lists are checked, e.g.: `className={…}` or `clsx(…)`, so that e.g.: keys or URLs
+/// that are built the same way are not reported.
+pub fn find(input: &[u8], helpers: &[String]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    for range in class_list_ranges(input, helpers) {
+        let value = &input[range.clone()];
+
+        // The first word continues an interpolation or concatenation, e.g.: `${size}px]`
+        if continues_expression(input, range.start) {
+            let len = value
+                .iter()
+                .position(u8::is_ascii_whitespace)
+                .unwrap_or(value.len());
+            report(input, range.start..range.start + len, &mut diagnostics);
+        }
+
+        // The last word is continued by an interpolation or concatenation, e.g.: `bg-${color}`
+        if continued_by_expression(input, range.end) {
+            let len = value
+                .iter()
+                .rev()
+                .position(u8::is_ascii_whitespace)
+                .unwrap_or(value.len());
+
+            // A single word that is both continued and continues is only reported once
+            let start = range.end - len;
+            if !diagnostics.last().is_some_and(|d| d.range.start == start) {
+                report(input, start..range.end, &mut diagnostics);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn report(input: &[u8], range: Range<usize>, diagnostics: &mut Vec<Diagnostic>) {
+    let fragment = &input[range.clone()];
+
+    if fragment.is_empty() || !fragment.iter().all(|c| is_class_like(*c)) {
+        return;
+    }
+
+    let Ok(fragment) = std::str::from_utf8(fragment) else {
+        return;
+    };
+
+    diagnostics.push(Diagnostic {
+        file: None,
+        range,
+        kind: DiagnosticKind::DynamicClass {
+            fragment: fragment.to_string(),
+        },
+    });
+}
+
+/// Characters of candidates, without quotes and brackets of the surrounding code.
+fn is_class_like(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"-_:[]#%./!&@*()+,".contains(&c)
+}
+
+/// Whether the class list at `start` directly follows an interpolation, e.g.: `${a}-500`, or a
+/// concatenation, e.g.: `a + "-500"`.
+pub(crate) fn continues_expression(input: &[u8], start: usize) -> bool {
+    match start.checked_sub(1).map(|idx| input[idx]) {
+        Some(b'}') => true,
+        Some(b'"' | b'\'' | b'`') => input[..start - 1]
+            .iter()
+            .rev()
+            .find(|c| !c.is_ascii_whitespace())
+            .is_some_and(|c| *c == b'+'),
+        _ => false,
+    }
+}
+
+/// Whether the class list that ends at `end` is directly followed by an interpolation, e.g.:
+/// `bg-${a}`, or a concatenation, e.g.: `"bg-" + a`.
+pub(crate) fn continued_by_expression(input: &[u8], end: usize) -> bool {
+    match input.get(end) {
+        Some(b'$') => input.get(end + 1) == Some(&b'{'),
+        Some(b'"' | b'\'' | b'`') => input[end + 1..]
+            .iter()
+            .find(|c| !c.is_ascii_whitespace())
+            .is_some_and(|c| *c == b'+'),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(input: &str) -> Vec<&str> {
+        let helpers: Vec<String> = vec!["clsx".into(), "cn".into()];
+
+        find(input.as_bytes(), &helpers)
+            .into_iter()
+            .map(|diagnostic| {
+                let fragment = &input[diagnostic.range];
+                assert_eq!(
+                    diagnostic.kind,
+                    DiagnosticKind::DynamicClass {
+                        fragment: fragment.to_string()
+                    }
+                );
+                fragment
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_should_find_interpolated_fragments() {
+        assert_eq!(
+            words("<div className={`flex bg-${color}-500 w-[${px}px]`}>"),
+            vec!["bg-", "-500", "w-[", "px]"]
+        );
+    }
+
+    #[test]
+    fn it_should_find_concatenated_fragments() {
+        assert_eq!(
+            words(r#"<div className={"text-" + size + " font-bold"}>"#),
+            vec!["text-"]
+        );
+        assert_eq!(words(r#"clsx('p-4', size + '-lg')"#), vec!["-lg"]);
+    }
+
+    #[test]
+    fn it_should_ignore_complete_classes() {
+        assert_eq!(
+            words("<div className={`flex ${active ? 'font-bold' : ''} p-4`}>"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(words(r#"<div class="flex" />"#), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn it_should_ignore_strings_outside_of_class_lists() {
+        assert_eq!(
+            words("let key = `item-${id}`; let url = '/api/' + id"),
+            Vec::<&str>::new()
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use bstr::ByteSlice;
+
+/// Heuristics to detect regions in content files that can't contain any candidates. These regions
+/// are blanked out before extraction, so that the `Extractor` doesn't have to walk through them
+/// byte by byte while producing junk candidates.
+///
+/// Blanking replaces the bytes with spaces, which means that positions of candidates in the
+/// remaining content are not affected.
+#[derive(Debug, Clone)]
+pub struct SkipHeuristics {
+    /// Skip `data:` URIs, e.g.: `data:image/svg+xml;base64,PHN2ZyB4bWxucz0i...`
+    ///
+    /// Data URIs that are part of an arbitrary value, e.g.: `bg-[url(data:image/png;base64,...)]`
+    /// are kept.
+    pub data_uris: bool,
+
+    /// Skip runs of base64 characters that are at least this many bytes long.
+    pub min_base64_length: Option<usize>,
+
+    /// Skip lines that are longer than this many bytes without containing any whitespace, e.g.:
+    /// minified bundles or inlined fonts.
+    pub max_line_length: Option<usize>,
+}
+
+impl Default for SkipHeuristics {
+    fn default() -> Self {
+        Self {
+            data_uris: true,
+            min_base64_length: Some(256),
+            max_line_length: Some(10 * 1024),
+        }
+    }
+}
+
+impl SkipHeuristics {
+    /// Blank out all regions matching the heuristics. Returns the number of bytes that were
+    /// skipped.
+    pub fn apply(&self, input: &mut [u8]) -> usize {
+        let mut skipped = 0;
+
+        if self.data_uris {
+            skipped += skip_data_uris(input);
+        }
+
+        if let Some(min) = self.min_base64_length {
+            skipped += skip_runs(input, min.max(1), is_base64);
+        }
+
+        if let Some(max) = self.max_line_length {
+            skipped += skip_runs(input, max.saturating_add(1), |c| !c.is_ascii_whitespace());
+        }
+
+        skipped
+    }
+}
+
+#[inline(always)]
+fn is_base64(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'+' | b'/' | b'=')
+}
+
+#[inline(always)]
+fn is_media_type(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'/' | b'+' | b'.' | b'-' | b';' | b'=')
+}
+
+/// Blank out all runs of bytes matching `predicate` that are at least `min_length` bytes long.
+fn skip_runs(input: &mut [u8], min_length: usize, predicate: impl Fn(u8) -> bool) -> usize {
+    let mut skipped = 0;
+    let mut start = 0;
+
+    while start < input.len() {
+        if !predicate(input[start]) {
+            start += 1;
+            continue;
+        }
+
+        let end = input[start..]
+            .iter()
+            .position(|c| !predicate(*c))
+            .map_or(input.len(), |len| start + len);
+
+        if end - start >= min_length {
+            input[start..end].fill(b' ');
+            skipped += end - start;
+        }
+
+        start = end;
+    }
+
+    skipped
+}
+
+fn skip_data_uris(input: &mut [u8]) -> usize {
+    let mut skipped = 0;
+    let positions: Vec<usize> = input.find_iter(b"data:").collect();
+
+    for start in positions {
+        // Ensure that we are at the start of a word, e.g.: `metadata:` is not a data URI.
+        if start > 0 && (input[start - 1].is_ascii_alphanumeric() || input[start - 1] == b'-') {
+            continue;
+        }
+
+        // Data URIs inside of arbitrary values should be kept as-is.
+        if is_in_arbitrary_value(&input[..start]) {
+            continue;
+        }
+
+        // The media type and parameters, e.g.: `image/svg+xml;base64` must be followed by a `,`.
+        let rest = &input[start + 5..];
+        let Some(comma) = rest.iter().take(256).position(|c| !is_media_type(*c)) else {
+            continue;
+        };
+
+        if rest[comma] != b',' {
+            continue;
+        }
+
+        let payload = start + 5 + comma + 1;
+        let end = input[payload..]
+            .iter()
+            .position(|c| {
+                c.is_ascii_whitespace()
+                    || matches!(c, b'"' | b'\'' | b'`' | b'(' | b')' | b'<' | b'>')
+            })
+            .map_or(input.len(), |len| payload + len);
+
+        input[start..end].fill(b' ');
+        skipped += end - start;
+    }
+
+    skipped
+}
+
+/// Whether the content right before a data URI opens an arbitrary value, e.g.: `bg-[url('`.
+fn is_in_arbitrary_value(before: &[u8]) -> bool {
+    let before = before
+        .strip_suffix(b"'")
+        .or_else(|| before.strip_suffix(b"\""))
+        .unwrap_or(before);
+    let before = before.strip_suffix(b"url(").unwrap_or(before);
+
+    before.ends_with(b"[")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn skip(input: &str, heuristics: SkipHeuristics) -> (String, usize) {
+        let mut input = input.as_bytes().to_vec();
+        let skipped = heuristics.apply(&mut input);
+        (String::from_utf8(input).unwrap(), skipped)
+    }
+
+    #[test]
+    fn it_should_skip_data_uris() {
+        let (result, skipped) = skip(
+            r#"<img class="flex" src="data:image/svg+xml;base64,PHN2Zz4=">"#,
+            Default::default(),
+        );
+
+        assert_eq!(
+            result,
+            format!(r#"<img class="flex" src="{}">"#, " ".repeat(34))
+        );
+        assert_eq!(skipped, 34);
+    }
+
+    #[test]
+    fn it_should_keep_data_uris_in_arbitrary_values() {
+        let input = r#"<div class="bg-[url('data:image/png;base64,iVBORw0KGgo=')]">"#;
+        let (result, skipped) = skip(input, Default::default());
+
+        assert_eq!(result, input);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn it_should_not_skip_words_ending_in_data() {
+        let input = "metadata:flex";
+        let (result, skipped) = skip(input, Default::default());
+
+        assert_eq!(result, input);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn it_should_skip_long_base64_runs() {
+        let blob = "QUJD".repeat(16);
+        let (result, skipped) = skip(
+            &format!("flex {} underline", blob),
+            SkipHeuristics {
+                data_uris: false,
+                min_base64_length: Some(64),
+                max_line_length: None,
+            },
+        );
+
+        assert_eq!(result, format!("flex {} underline", " ".repeat(64)));
+        assert_eq!(skipped, 64);
+    }
+
+    #[test]
+    fn it_should_skip_long_lines_without_whitespace() {
+        let line = "a-b;".repeat(8);
+        let (result, skipped) = skip(
+            &format!("flex\n{}\nunderline", line),
+            SkipHeuristics {
+                data_uris: false,
+                min_base64_length: None,
+                max_line_length: Some(16),
+            },
+        );
+
+        assert_eq!(result, format!("flex\n{}\nunderline", " ".repeat(32)));
+        assert_eq!(skipped, 32);
+    }
+
+    #[test]
+    fn it_should_keep_short_lines() {
+        let input = "flex items-center\nunderline";
+        let (result, skipped) = skip(
+            input,
+            SkipHeuristics {
+                data_uris: true,
+                min_base64_length: Some(16),
+                max_line_length: Some(32),
+            },
+        );
+
+        assert_eq!(result, input);
+        assert_eq!(skipped, 0);
+    }
+}
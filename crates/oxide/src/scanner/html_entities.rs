@@ -0,0 +1,191 @@
+use crate::scanner::offset_map::OffsetMap;
+use bstr::ByteSlice;
+
+/// Extensions of HTML-like templates whose attribute values can contain HTML entities.
+pub const EXTENSIONS: &[&str] = &[
+    "html",
+    "htm",
+    "xhtml",
+    "erb",
+    "php",
+    "twig",
+    "liquid",
+    "njk",
+    "hbs",
+    "handlebars",
+    "mustache",
+    "ejs",
+    "jinja",
+    "j2",
+    "cshtml",
+    "razor",
+    "xml",
+];
+
+/// Named entities that can reasonably appear in class names. All other entities are kept as-is.
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("lsqb", '['),
+    ("lbrack", '['),
+    ("rsqb", ']'),
+    ("rbrack", ']'),
+    ("lpar", '('),
+    ("rpar", ')'),
+    ("lcub", '{'),
+    ("lbrace", '{'),
+    ("rcub", '}'),
+    ("rbrace", '}'),
+    ("colon", ':'),
+    ("semi", ';'),
+    ("comma", ','),
+    ("period", '.'),
+    ("sol", '/'),
+    ("bsol", '\\'),
+    ("excl", '!'),
+    ("num", '#'),
+    ("percnt", '%'),
+    ("ast", '*'),
+    ("plus", '+'),
+    ("equals", '='),
+    ("lowbar", '_'),
+    ("grave", '`'),
+    ("vert", '|'),
+    ("dollar", '$'),
+    ("quest", '?'),
+    ("commat", '@'),
+    ("Hat", '^'),
+    ("tilde", '~'),
+];
+
+/// Decode HTML entities inside of quoted attribute values, e.g.: `class="[&amp;_p]:mt-2"` becomes
+/// `class="[&_p]:mt-2"`. Returns `None` when there is nothing to decode.
+pub fn decode_attribute_values(input: &[u8]) -> Option<(Vec<u8>, OffsetMap)> {
+    input.find_byte(b'&')?;
+
+    let mut output = Vec::with_capacity(input.len());
+    let mut offsets = OffsetMap::default();
+    let mut quote = None;
+    let mut idx = 0;
+
+    while idx < input.len() {
+        let c = input[idx];
+
+        match quote {
+            // The end of the attribute value
+            Some(q) if c == q => quote = None,
+
+            Some(_) if c == b'&' => {
+                if let Some((decoded, len)) = decode_entity(&input[idx..]) {
+                    let mut buf = [0; 4];
+                    output.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+                    idx += len;
+                    offsets.push(output.len(), idx);
+                    continue;
+                }
+            }
+
+            Some(_) => {}
+
+            // The start of a quoted attribute value, e.g.: `class="` or `class = '`
+            None if c == b'=' => {
+                let value = input[idx + 1..]
+                    .iter()
+                    .position(|c| !c.is_ascii_whitespace())
+                    .map(|offset| idx + 1 + offset);
+
+                if let Some(value) = value.filter(|value| matches!(input[*value], b'"' | b'\'')) {
+                    output.extend_from_slice(&input[idx..=value]);
+                    quote = Some(input[value]);
+                    idx = value + 1;
+                    continue;
+                }
+            }
+
+            None => {}
+        }
+
+        output.push(c);
+        idx += 1;
+    }
+
+    (!offsets.is_identity()).then_some((output, offsets))
+}
+
+/// Decode a single entity at the start of `input`, e.g.: `&amp;`, `&#38;` or `&#x26;`. Returns the
+/// decoded character and the length of the entity.
+fn decode_entity(input: &[u8]) -> Option<(char, usize)> {
+    let end = input.iter().take(16).position(|c| *c == b';')?;
+    let name = std::str::from_utf8(&input[1..end]).ok()?;
+
+    let decoded = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+
+            char::from_u32(code).filter(|c| *c != '\0')?
+        }
+        None => NAMED_ENTITIES
+            .iter()
+            .find(|(entity, _)| *entity == name)
+            .map(|(_, c)| *c)?,
+    };
+
+    Some((decoded, end + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(input: &str) -> String {
+        decode_attribute_values(input.as_bytes())
+            .map(|(output, _)| String::from_utf8(output).unwrap())
+            .unwrap_or_else(|| input.to_string())
+    }
+
+    #[test]
+    fn it_should_decode_entities_in_attribute_values() {
+        assert_eq!(
+            decode(r#"<p class="[&amp;_p]:mt-2 content-[&quot;x&quot;]">"#),
+            r#"<p class="[&_p]:mt-2 content-["x"]">"#
+        );
+        assert_eq!(
+            decode("<p class = 'content-[&#8594;] before:content-[&#x2713;]'>"),
+            "<p class = 'content-[→] before:content-[✓]'>"
+        );
+    }
+
+    #[test]
+    fn it_should_keep_entities_outside_of_attribute_values() {
+        let input = r#"<p class="flex">Tom &amp; Jerry</p>"#;
+        assert_eq!(decode(input), input);
+        assert_eq!(decode_attribute_values(input.as_bytes()), None);
+    }
+
+    #[test]
+    fn it_should_keep_unknown_and_invalid_entities() {
+        let input = r#"<p class="&unknown; &#0; &#xZZ; &amp flex">"#;
+        assert_eq!(decode(input), input);
+    }
+
+    #[test]
+    fn it_should_map_offsets_back_to_the_original_content() {
+        let input = r#"<p class="a [&amp;_p]:mt-2">"#;
+        let (output, offsets) = decode_attribute_values(input.as_bytes()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let start = output.find("[&_p]").unwrap();
+        let end = start + "[&_p]:mt-2".len();
+        assert_eq!(
+            &input[offsets.to_original(start)..offsets.to_original(end)],
+            "[&amp;_p]:mt-2"
+        );
+    }
+}
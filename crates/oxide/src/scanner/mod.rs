@@ -1,2 +1,13 @@
 pub mod allowed_paths;
+pub mod candidate_store;
+pub mod comments;
+pub mod custom_extractors;
 pub mod detect_sources;
+pub mod directives;
+pub mod dynamic_classes;
+pub mod heuristics;
+pub mod html_entities;
+pub mod offset_map;
+pub mod string_literals;
+pub mod stylesheets;
+pub mod transformers;
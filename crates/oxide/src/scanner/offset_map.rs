@@ -0,0 +1,126 @@
+use bstr::ByteSlice;
+
+/// Maps byte offsets in preprocessed content back to byte offsets in the original content.
+///
+/// Preprocessing steps that change the length of the content, e.g.: decoding `&amp;` to `&`,
+/// record an anchor after every change. Offsets between two anchors move in lockstep.
+///
+/// When multiple preprocessing steps change the content, each step adds its own layer of anchors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    /// Layers of `(preprocessed, original)` offset pairs, sorted by both offsets. The last layer
+    /// belongs to the last preprocessing step.
+    layers: Vec<Vec<(usize, usize)>>,
+}
+
+impl OffsetMap {
+    /// Record that `preprocessed` corresponds to `original`. Anchors have to be pushed in order.
+    pub fn push(&mut self, preprocessed: usize, original: usize) {
+        if self.layers.is_empty() {
+            self.layers.push(vec![]);
+        }
+
+        let anchors = self.layers.last_mut().unwrap();
+        debug_assert!(anchors
+            .last()
+            .map_or(true, |(p, o)| *p <= preprocessed && *o <= original));
+
+        anchors.push((preprocessed, original));
+    }
+
+    /// Add the offsets of a later preprocessing step, which ran on the output of this one.
+    pub fn then(&mut self, later: OffsetMap) {
+        self.layers.extend(later.layers);
+    }
+
+    /// Whether offsets are the same in the preprocessed and original content.
+    pub fn is_identity(&self) -> bool {
+        self.layers.iter().flatten().all(|(p, o)| p == o)
+    }
+
+    pub fn to_original(&self, pos: usize) -> usize {
+        self.layers.iter().rev().fold(pos, |pos, anchors| {
+            match anchors.partition_point(|(p, _)| *p <= pos) {
+                0 => pos,
+                idx => {
+                    let (p, o) = anchors[idx - 1];
+                    o + (pos - p)
+                }
+            }
+        })
+    }
+}
+
+/// Replace all occurrences of `from` with `to`, and record the offsets of the replacements.
+/// Returns `None` when nothing was replaced.
+pub fn replace_all(input: &[u8], from: &[u8], to: &[u8]) -> Option<(Vec<u8>, OffsetMap)> {
+    let mut matches = input.find_iter(from).peekable();
+    matches.peek()?;
+
+    let mut output = Vec::with_capacity(input.len());
+    let mut offsets = OffsetMap::default();
+    let mut last = 0;
+
+    for idx in matches {
+        output.extend_from_slice(&input[last..idx]);
+        output.extend_from_slice(to);
+        last = idx + from.len();
+        offsets.push(output.len(), last);
+    }
+
+    output.extend_from_slice(&input[last..]);
+
+    Some((output, offsets))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_should_map_offsets_back_to_the_original_content() {
+        // `a &amp; b` was decoded to `a & b`
+        let mut map = OffsetMap::default();
+        map.push(3, 7);
+
+        assert_eq!(map.to_original(0), 0);
+        assert_eq!(map.to_original(2), 2);
+        assert_eq!(map.to_original(3), 7);
+        assert_eq!(map.to_original(5), 9);
+        assert!(!map.is_identity());
+        assert!(OffsetMap::default().is_identity());
+    }
+
+    #[test]
+    fn it_should_map_offsets_through_multiple_layers() {
+        // `\"&amp;\"` was unescaped to `"&amp;"`, and then decoded to `"&"`
+        let mut map = OffsetMap::default();
+        map.push(1, 2);
+        map.push(7, 9);
+
+        let mut later = OffsetMap::default();
+        later.push(2, 6);
+        map.then(later);
+
+        assert_eq!(map.to_original(0), 0);
+        assert_eq!(map.to_original(1), 2);
+        assert_eq!(map.to_original(2), 7);
+        assert_eq!(map.to_original(3), 9);
+    }
+
+    #[test]
+    fn it_should_record_replacements() {
+        let input = "<div class:px-4={a} class:flex>";
+        let (output, map) = replace_all(input.as_bytes(), b" class:", b" ").unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(output, "<div px-4={a} flex>");
+        for candidate in ["px-4", "flex", "{a}", ">"] {
+            let start = output.find(candidate).unwrap();
+            let original = map.to_original(start)..map.to_original(start + candidate.len());
+            assert_eq!(&input[original], candidate);
+        }
+
+        assert_eq!(replace_all(b"<div>", b" class:", b" "), None);
+    }
+}
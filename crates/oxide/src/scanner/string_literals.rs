@@ -0,0 +1,149 @@
+use crate::scanner::offset_map::OffsetMap;
+use bstr::ByteSlice;
+
+/// Unescape all escape sequences of JSON and JavaScript string literals, e.g.: `content-[\"hi\"]`
+/// becomes `content-["hi"]`.
+///
+/// Every `\` is treated as the start of an escape sequence, which is why this should only be used
+/// for content where classes live inside of string literals, e.g.: JSON files. Returns `None` when
+/// there is nothing to unescape.
+pub fn unescape(input: &[u8]) -> Option<(Vec<u8>, OffsetMap)> {
+    input.find_byte(b'\\')?;
+
+    let mut output = Vec::with_capacity(input.len());
+    let mut offsets = OffsetMap::default();
+    let mut idx = 0;
+
+    while idx < input.len() {
+        if input[idx] != b'\\' || idx + 1 == input.len() {
+            output.push(input[idx]);
+            idx += 1;
+            continue;
+        }
+
+        let (unescaped, len) = match input[idx + 1] {
+            b'n' => ('\n', 2),
+            b'r' => ('\r', 2),
+            b't' => ('\t', 2),
+            b'b' => ('\u{8}', 2),
+            b'f' => ('\u{c}', 2),
+            b'v' => ('\u{b}', 2),
+            b'0' if !input.get(idx + 2).is_some_and(u8::is_ascii_digit) => ('\0', 2),
+            b'u' => match unescape_unicode(&input[idx..]) {
+                Some(unescaped) => unescaped,
+
+                // Invalid unicode escapes are kept as-is
+                None => {
+                    output.push(b'\\');
+                    idx += 1;
+                    continue;
+                }
+            },
+
+            // All other escaped characters represent themselves, e.g.: `\"`, `\\` or `\/`. A
+            // multi-byte character is copied as-is on the next iteration.
+            c if c.is_ascii() => (c as char, 2),
+            _ => {
+                idx += 1;
+                offsets.push(output.len(), idx);
+                continue;
+            }
+        };
+
+        let mut buf = [0; 4];
+        output.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+        idx += len;
+        offsets.push(output.len(), idx);
+    }
+
+    (!offsets.is_identity()).then_some((output, offsets))
+}
+
+/// Unescape `\uXXXX`, `\u{XXXXX}` and surrogate pairs, e.g.: `\ud83d\ude00`. Returns the character
+/// and the length of the escape sequence.
+fn unescape_unicode(input: &[u8]) -> Option<(char, usize)> {
+    if input.get(2) == Some(&b'{') {
+        let end = input.iter().take(12).position(|c| *c == b'}')?;
+        let code = parse_hex(&input[3..end])?;
+        return Some((char::from_u32(code)?, end + 1));
+    }
+
+    let high = parse_hex(input.get(2..6)?)?;
+    if !(0xd800..0xdc00).contains(&high) {
+        return Some((char::from_u32(high)?, 6));
+    }
+
+    // The high surrogate has to be followed by a low surrogate
+    if input.get(6..8) != Some(b"\\u") {
+        return None;
+    }
+
+    let low = parse_hex(input.get(8..12)?)?;
+    if !(0xdc00..0xe000).contains(&low) {
+        return None;
+    }
+
+    let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+    Some((char::from_u32(code)?, 12))
+}
+
+fn parse_hex(input: &[u8]) -> Option<u32> {
+    if input.is_empty() || !input.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    u32::from_str_radix(std::str::from_utf8(input).ok()?, 16).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unescape_str(input: &str) -> String {
+        unescape(input.as_bytes())
+            .map(|(output, _)| String::from_utf8(output).unwrap())
+            .unwrap_or_else(|| input.to_string())
+    }
+
+    #[test]
+    fn it_should_unescape_string_literals() {
+        assert_eq!(
+            unescape_str(r#""<p class='content-[\"hi\"] bg-[url(\"\/a.png\")]'>""#),
+            r#""<p class='content-["hi"] bg-[url("/a.png")]'>""#
+        );
+        assert_eq!(
+            unescape_str(r#"flex\nunderline\tcontent-['a\\_b']"#),
+            "flex\nunderline\tcontent-['a\\_b']"
+        );
+    }
+
+    #[test]
+    fn it_should_unescape_unicode_escapes() {
+        assert_eq!(unescape_str(r#"content-['\u2192']"#), "content-['→']");
+        assert_eq!(unescape_str(r#"content-['\u{1F600}']"#), "content-['😀']");
+        assert_eq!(
+            unescape_str(r#"content-['\ud83d\ude00']"#),
+            "content-['😀']"
+        );
+
+        // Invalid escapes are kept
+        assert_eq!(
+            unescape_str(r#"\u12 \ud83d \u{zz}"#),
+            r#"\u12 \ud83d \u{zz}"#
+        );
+    }
+
+    #[test]
+    fn it_should_map_offsets_back_to_the_original_content() {
+        let input = r#"{"a": "x content-[\"hi\"] y"}"#;
+        let (output, offsets) = unescape(input.as_bytes()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let start = output.find("content").unwrap();
+        let end = start + r#"content-["hi"]"#.len();
+        assert_eq!(
+            &input[offsets.to_original(start)..offsets.to_original(end)],
+            r#"content-[\"hi\"]"#
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use bstr::ByteSlice;
+use std::ops::Range;
+
+/// Extensions of stylesheets, these are only scanned for the candidates of their `@apply` rules.
+pub const EXTENSIONS: &[&str] = &["css", "scss", "sass", "less", "styl"];
+
+/// Extensions of components that can contain `<style>` blocks.
+pub const COMPONENT_EXTENSIONS: &[&str] = &["vue", "svelte", "astro"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Plain CSS, only block comments
+    Css,
+
+    /// SCSS and Less, block and line comments
+    Scss,
+
+    /// Sass and Stylus, where a line break ends a rule
+    Indented,
+}
+
+impl Syntax {
+    pub fn from_extension(extension: &str) -> Self {
+        match extension {
+            "scss" | "less" => Syntax::Scss,
+            "sass" | "styl" | "stylus" => Syntax::Indented,
+            _ => Syntax::Css,
+        }
+    }
+}
+
+/// Blank out everything except for the candidates of `@apply` rules, e.g.: `.btn { @apply px-4
+/// hover:underline; }` only keeps `px-4 hover:underline`.
+///
+/// The content is blanked in place, which means that offsets don't change.
+pub fn keep_apply_rules(input: &mut [u8], syntax: Syntax) {
+    let mut kept_until = 0;
+    let mut idx = 0;
+
+    while idx < input.len() {
+        match input[idx] {
+            // Block comments, e.g.: `/* @apply flex; */`
+            b'/' if input.get(idx + 1) == Some(&b'*') => {
+                idx = match input[idx + 2..].find(b"*/") {
+                    Some(end) => idx + 2 + end + 2,
+                    None => input.len(),
+                };
+            }
+
+            // Line comments, e.g.: `// @apply flex;`, but not URLs, e.g.: `url(https://…)`
+            b'/' if syntax != Syntax::Css
+                && input.get(idx + 1) == Some(&b'/')
+                && (idx == 0 || input[idx - 1] != b':') =>
+            {
+                idx = match input[idx..].find_byte(b'\n') {
+                    Some(end) => idx + end,
+                    None => input.len(),
+                };
+            }
+
+            b'@' if is_apply_rule(input, idx) => {
+                let start = idx + b"@apply".len();
+                let end = input[start..]
+                    .iter()
+                    .position(|c| match c {
+                        b';' | b'{' | b'}' => true,
+                        b'\n' => syntax == Syntax::Indented,
+                        _ => false,
+                    })
+                    .map_or(input.len(), |end| start + end);
+
+                input[kept_until..start].fill(b' ');
+                blank_important_flags(&mut input[start..end]);
+
+                kept_until = end;
+                idx = end;
+            }
+
+            _ => idx += 1,
+        }
+    }
+
+    input[kept_until..].fill(b' ');
+}
+
+/// Whether an `@apply` rule starts at `idx`.
+fn is_apply_rule(input: &[u8], idx: usize) -> bool {
+    input[idx..].starts_with(b"@apply")
+        && input
+            .get(idx + b"@apply".len())
+            .is_some_and(u8::is_ascii_whitespace)
+        && (idx == 0
+            || matches!(input[idx - 1], b'{' | b'}' | b';')
+            || input[idx - 1].is_ascii_whitespace())
+}
+
+/// Blank out the `!important` flag of legacy `@apply` rules, e.g.: `@apply flex !important`.
+fn blank_important_flags(params: &mut [u8]) {
+    let mut idx = 0;
+
+    while let Some(pos) = params[idx..].find(b"!important") {
+        let start = idx + pos;
+        let end = start + b"!important".len();
+
+        let before = start == 0 || params[start - 1].is_ascii_whitespace();
+        let after = end == params.len() || params[end].is_ascii_whitespace();
+        if before && after {
+            params[start..end].fill(b' ');
+        }
+
+        idx = end;
+    }
+}
+
+/// The ranges of the contents of all `<style>` blocks in a component, together with their syntax
+/// based on the `lang` attribute, e.g.: `<style lang="scss">`.
+pub fn style_blocks(input: &[u8]) -> Vec<(Range<usize>, Syntax)> {
+    let mut blocks = vec![];
+    let mut idx = 0;
+
+    while let Some(pos) = find_tag(&input[idx..], b"<style") {
+        let tag_start = idx + pos;
+        let Some(tag_end) = input[tag_start..]
+            .find_byte(b'>')
+            .map(|end| tag_start + end)
+        else {
+            break;
+        };
+
+        let syntax =
+            lang_attribute(&input[tag_start..tag_end]).map_or(Syntax::Css, Syntax::from_extension);
+
+        let start = tag_end + 1;
+        let end = find_tag(&input[start..], b"</style").map_or(input.len(), |end| start + end);
+
+        blocks.push((start..end, syntax));
+        idx = end;
+    }
+
+    blocks
+}
+
+/// Find an opening or closing tag, ignoring ASCII case, e.g.: `<style>` or `<STYLE scoped>`.
+fn find_tag(input: &[u8], tag: &[u8]) -> Option<usize> {
+    let mut idx = 0;
+
+    while let Some(pos) = input[idx..].find_byte(b'<') {
+        let start = idx + pos;
+        let end = start + tag.len();
+
+        if input
+            .get(start..end)
+            .is_some_and(|name| name.eq_ignore_ascii_case(tag))
+            && input
+                .get(end)
+                .is_some_and(|c| *c == b'>' || *c == b'/' || c.is_ascii_whitespace())
+        {
+            return Some(start);
+        }
+
+        idx = start + 1;
+    }
+
+    None
+}
+
+/// The value of the `lang` attribute of a tag, e.g.: `scss` in `<style lang="scss"`.
+fn lang_attribute(tag: &[u8]) -> Option<&str> {
+    let start = tag.find(b"lang=")? + b"lang=".len();
+    let quote = *tag.get(start)?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+
+    let end = tag[start + 1..].find_byte(quote)?;
+    std::str::from_utf8(&tag[start + 1..start + 1 + end]).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keep(input: &str, syntax: Syntax) -> String {
+        let mut output = input.as_bytes().to_vec();
+        keep_apply_rules(&mut output, syntax);
+
+        assert_eq!(output.len(), input.len());
+        String::from_utf8(output).unwrap()
+    }
+
+    fn words(input: &str) -> Vec<&str> {
+        input.split_whitespace().collect()
+    }
+
+    #[test]
+    fn it_should_only_keep_apply_rules() {
+        let output = keep(
+            r#"
+                .btn {
+                    color: red;
+                    @apply px-4 hover:underline !font-bold;
+                }
+                .card { @apply rounded-[4px] md:p-4! }
+                .item{@apply flex}
+            "#,
+            Syntax::Css,
+        );
+
+        assert_eq!(
+            words(&output),
+            vec![
+                "px-4",
+                "hover:underline",
+                "!font-bold",
+                "rounded-[4px]",
+                "md:p-4!",
+                "flex"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_drop_legacy_important_flags() {
+        let output = keep(".btn { @apply flex !important; }", Syntax::Css);
+        assert_eq!(words(&output), vec!["flex"]);
+    }
+
+    #[test]
+    fn it_should_skip_comments() {
+        let output = keep(
+            "/* @apply flex; */ .a { background: url(https://a.com/x.png); @apply underline; }",
+            Syntax::Css,
+        );
+        assert_eq!(words(&output), vec!["underline"]);
+
+        let output = keep(
+            ".a {\n  // @apply flex;\n  @apply underline;\n}",
+            Syntax::Scss,
+        );
+        assert_eq!(words(&output), vec!["underline"]);
+    }
+
+    #[test]
+    fn it_should_end_rules_at_line_breaks_in_indented_syntaxes() {
+        let output = keep(".btn\n  @apply px-4 py-2\n  color: red\n", Syntax::Indented);
+        assert_eq!(words(&output), vec!["px-4", "py-2"]);
+    }
+
+    #[test]
+    fn it_should_not_match_other_at_rules() {
+        let output = keep(
+            "@applyx flex; a@apply underline; @media print {}",
+            Syntax::Css,
+        );
+        assert_eq!(words(&output), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn it_should_find_style_blocks() {
+        let input = r#"<template><div class="flex"></div></template>
+<style scoped>.a { @apply underline; }</style>
+<STYLE lang="sass">.b
+  @apply px-4
+</STYLE>"#;
+
+        let blocks = style_blocks(input.as_bytes());
+        assert_eq!(blocks.len(), 2);
+
+        let (range, syntax) = &blocks[0];
+        assert_eq!(&input[range.clone()], ".a { @apply underline; }");
+        assert_eq!(*syntax, Syntax::Css);
+
+        let (range, syntax) = &blocks[1];
+        assert_eq!(&input[range.clone()], ".b\n  @apply px-4\n");
+        assert_eq!(*syntax, Syntax::Indented);
+    }
+}
@@ -0,0 +1,164 @@
+use fxhash::{FxHashMap, FxHasher};
+use std::fmt;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Maximum amount of contents that are passed to a transformer at once.
+pub const BATCH_SIZE: usize = 64;
+
+/// Content that has to be transformed before extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformInput {
+    /// The file of the content, not set for in-memory content
+    pub file: Option<PathBuf>,
+
+    pub content: String,
+}
+
+/// Turns content that the `Extractor` can't handle into text to extract candidates from, e.g.: by
+/// compiling a template with its real compiler.
+pub trait Transformer: Send + Sync {
+    /// Transform a batch of contents. Returns one output per input, `None` keeps the original
+    /// content. Missing outputs keep the original content as well.
+    fn transform(&self, batch: &[TransformInput]) -> Vec<Option<String>>;
+}
+
+impl<F> Transformer for F
+where
+    F: Fn(&[TransformInput]) -> Vec<Option<String>> + Send + Sync,
+{
+    fn transform(&self, batch: &[TransformInput]) -> Vec<Option<String>> {
+        self(batch)
+    }
+}
+
+/// All registered transformers, by extension.
+#[derive(Clone, Default)]
+pub struct Transformers(FxHashMap<String, Arc<dyn Transformer>>);
+
+impl Transformers {
+    pub fn get(&self, extension: Option<&str>) -> Option<&Arc<dyn Transformer>> {
+        self.0.get(extension?)
+    }
+
+    /// Register the transformer of an extension, `None` removes it.
+    pub fn set(&mut self, extension: String, transformer: Option<Arc<dyn Transformer>>) {
+        match transformer {
+            Some(transformer) => self.0.insert(extension, transformer),
+            None => self.0.remove(&extension),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Transformers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Transformed content of files, together with the hash of the content it was computed from.
+#[derive(Debug, Clone, Default)]
+pub struct TransformCache(FxHashMap<PathBuf, (u64, Option<Arc<str>>)>);
+
+impl TransformCache {
+    /// The cached output of `file`, if its content didn't change since it was transformed.
+    pub fn get(&self, file: &Path, hash: u64) -> Option<Option<Arc<str>>> {
+        match self.0.get(file) {
+            Some((cached, output)) if *cached == hash => Some(output.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, file: PathBuf, hash: u64, output: Option<Arc<str>>) {
+        self.0.insert(file, (hash, output));
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+pub fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write(content);
+    hasher.write_usize(content.len());
+    hasher.finish()
+}
+
+/// Run the transformer over all inputs in batches of `BATCH_SIZE`. Returns exactly one output per
+/// input.
+pub fn transform_batched(
+    transformer: &dyn Transformer,
+    inputs: &[TransformInput],
+) -> Vec<Option<String>> {
+    inputs
+        .chunks(BATCH_SIZE)
+        .flat_map(|batch| {
+            let mut outputs = transformer.transform(batch);
+            outputs.resize(batch.len(), None);
+            outputs
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn input(content: &str) -> TransformInput {
+        TransformInput {
+            file: None,
+            content: content.into(),
+        }
+    }
+
+    #[test]
+    fn it_should_transform_in_batches() {
+        let calls = AtomicUsize::new(0);
+        let transformer = |batch: &[TransformInput]| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            batch
+                .iter()
+                .map(|input| Some(input.content.to_uppercase()))
+                .collect()
+        };
+
+        let inputs: Vec<_> = (0..BATCH_SIZE + 1).map(|_| input("a")).collect();
+        let outputs = transform_batched(&transformer, &inputs);
+
+        assert_eq!(outputs.len(), BATCH_SIZE + 1);
+        assert!(outputs.iter().all(|output| output.as_deref() == Some("A")));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn it_should_keep_missing_outputs() {
+        let transformer = |_: &[TransformInput]| vec![Some("b".to_string())];
+
+        assert_eq!(
+            transform_batched(&transformer, &[input("a"), input("c")]),
+            vec![Some("b".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn it_should_invalidate_changed_content() {
+        let mut cache = TransformCache::default();
+        let file = Path::new("/app/page.mdx");
+
+        cache.insert(file.into(), content_hash(b"a"), Some("b".into()));
+
+        assert_eq!(cache.get(file, content_hash(b"a")), Some(Some("b".into())));
+        assert_eq!(cache.get(file, content_hash(b"c")), None);
+        assert_eq!(
+            cache.get(Path::new("/app/other.mdx"), content_hash(b"a")),
+            None
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::candidate::split_top_level;
+use fxhash::{FxHashMap, FxHashSet};
+
+/// Maximum amount of suggestions per typo.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Utilities shorter than this are never reported, because too many words are that close to a
+/// valid class, e.g.: `the` and `h-4`.
+const MIN_LENGTH: usize = 4;
+
+/// A candidate that is close to, but not one of the valid classes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Typo {
+    pub candidate: String,
+
+    /// The closest valid classes, e.g.: `hover:bg-green-500` for
+    /// `hover:bg-gren-500`
+    pub suggestions: Vec<String>,
+}
+
+/// A fuzzy index over the valid classes supplied by the compiler, e.g.: `bg-green-500` or
+/// `items-center`.
+#[derive(Debug, Clone, Default)]
+pub struct TypoIndex {
+    valid: FxHashSet<String>,
+    tree: BkTree,
+}
+
+impl TypoIndex {
+    pub fn new(classes: impl IntoIterator<Item = String>) -> Self {
+        let mut index = Self::default();
+
+        for class in classes {
+            if index.valid.insert(class.clone()) {
+                index.tree.insert(class);
+            }
+        }
+
+        index
+    }
+
+    /// Suggestions for the candidate when its utility is not valid, but close to valid utilities.
+    /// The variants, e.g.: `hover:`, and the important and negative markers are kept as-is.
+    ///
+    /// Only candidates that look like utilities are checked, i.e.: candidates with a variant or a
+    /// `-` inside of the utility.
+    pub fn check(&self, candidate: &str) -> Option<Typo> {
+        let utility = split_top_level(candidate, b':').pop()?;
+        let before = &candidate[..candidate.len() - utility.len()];
+
+        let (utility, important) = match utility.strip_suffix('!') {
+            Some(utility) => (utility, "!"),
+            None => (utility, ""),
+        };
+
+        let start = utility.len() - utility.trim_start_matches(|c| c == '!' || c == '-').len();
+        let (markers, utility) = utility.split_at(start);
+
+        // Plain words, e.g.: `blocks` or `tables`, are too often prose or identifiers. Utilities
+        // with a variant or a value, e.g.: `hover:blocks` or `bg-gren-500`, are checked.
+        if before.is_empty() && !utility.contains('-') {
+            return None;
+        }
+
+        if utility.len() < MIN_LENGTH
+            || self.valid.contains(utility)
+            || !utility
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'-')
+        {
+            return None;
+        }
+
+        // One edit for short utilities, two edits for longer ones
+        let max_distance = if utility.len() < 8 { 1 } else { 2 };
+
+        let mut matches = self.tree.find(utility, max_distance);
+        matches.sort();
+
+        // Only the closest classes are suggested
+        let closest = matches.first()?.0;
+        matches.retain(|(distance, _)| *distance == closest);
+
+        Some(Typo {
+            candidate: candidate.to_string(),
+            suggestions: matches
+                .into_iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|(_, suggestion)| format!("{}{}{}{}", before, markers, suggestion, important))
+                .collect(),
+        })
+    }
+}
+
+/// A BK-tree over words, using the Levenshtein distance.
+#[derive(Debug, Clone, Default)]
+struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+#[derive(Debug, Clone)]
+struct BkNode {
+    word: String,
+
+    /// Children by their distance to this node
+    children: FxHashMap<usize, usize>,
+}
+
+impl BkTree {
+    fn insert(&mut self, word: String) {
+        let next = self.nodes.len();
+        if next == 0 {
+            self.nodes.push(BkNode {
+                word,
+                children: Default::default(),
+            });
+            return;
+        }
+
+        let mut node = 0;
+        loop {
+            let distance = levenshtein(&self.nodes[node].word, &word);
+            if distance == 0 {
+                return;
+            }
+
+            match self.nodes[node].children.get(&distance) {
+                Some(child) => node = *child,
+                None => {
+                    self.nodes[node].children.insert(distance, next);
+                    self.nodes.push(BkNode {
+                        word,
+                        children: Default::default(),
+                    });
+                    return;
+                }
+            }
+        }
+    }
+
+    /// All words within `max_distance` of `word`, together with their distance.
+    fn find(&self, word: &str, max_distance: usize) -> Vec<(usize, &str)> {
+        let mut matches = vec![];
+        let mut stack = match self.nodes.is_empty() {
+            true => vec![],
+            false => vec![0],
+        };
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let distance = levenshtein(&node.word, word);
+
+            if distance <= max_distance {
+                matches.push((distance, node.word.as_str()));
+            }
+
+            // Only children within the distance range can contain matches
+            let range = distance.saturating_sub(max_distance)..=distance + max_distance;
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(distance, _)| range.contains(distance))
+                    .map(|(_, child)| *child),
+            );
+        }
+
+        matches
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn index() -> TypoIndex {
+        TypoIndex::new(
+            [
+                "bg-green-500",
+                "bg-green-600",
+                "bg-red-500",
+                "items-center",
+                "items-start",
+                "flex",
+                "mx-4",
+                "block",
+                "table",
+                "border",
+                "fixed",
+            ]
+            .map(Into::into),
+        )
+    }
+
+    fn suggestions(candidate: &str) -> Option<Vec<String>> {
+        index().check(candidate).map(|typo| typo.suggestions)
+    }
+
+    #[test]
+    fn it_should_compute_the_levenshtein_distance() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("flex", ""), 4);
+        assert_eq!(levenshtein("bg-gren-500", "bg-green-500"), 1);
+        assert_eq!(levenshtein("itmes-center", "items-center"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn it_should_suggest_close_classes() {
+        assert_eq!(
+            suggestions("bg-gren-500"),
+            Some(vec!["bg-green-500".into()])
+        );
+        assert_eq!(
+            suggestions("itmes-center"),
+            Some(vec!["items-center".into()])
+        );
+    }
+
+    #[test]
+    fn it_should_keep_variants_and_markers() {
+        assert_eq!(
+            suggestions("md:hover:bg-gren-500!"),
+            Some(vec!["md:hover:bg-green-500!".into()])
+        );
+        assert_eq!(suggestions("-mx-44"), Some(vec!["-mx-4".into()]));
+        assert_eq!(
+            suggestions("!-itmes-center"),
+            Some(vec!["!-items-center".into()])
+        );
+    }
+
+    #[test]
+    fn it_should_suggest_all_equally_close_classes() {
+        assert_eq!(
+            suggestions("bg-green-x00"),
+            Some(vec!["bg-green-500".into(), "bg-green-600".into()])
+        );
+    }
+
+    #[test]
+    fn it_should_ignore_valid_and_unrelated_candidates() {
+        assert_eq!(suggestions("bg-green-500"), None);
+        assert_eq!(suggestions("hover:flex"), None);
+        assert_eq!(suggestions("flx"), None);
+        assert_eq!(suggestions("const"), None);
+        assert_eq!(suggestions("bg-[#0088cc]"), None);
+    }
+
+    #[test]
+    fn it_should_ignore_prose() {
+        for word in ["blocks", "tables", "borders", "fixes"] {
+            assert_eq!(suggestions(word), None, "{} should be ignored", word);
+        }
+
+        assert_eq!(suggestions("md:blocks"), Some(vec!["md:block".into()]));
+    }
+}
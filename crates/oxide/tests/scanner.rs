@@ -364,4 +364,936 @@ mod scanner {
 
         assert_eq!(candidates, vec!["content-['foo.styl']"]);
     }
+
+    #[test]
+    fn it_should_skip_data_uris_and_report_skipped_bytes() {
+        let dir = tempdir().unwrap().into_path();
+        fs::write(
+            dir.join("index.html"),
+            r#"<img class="flex" src="data:image/svg+xml;base64,PHN2ZyB4bWxucz0i">"#,
+        )
+        .unwrap();
+
+        let mut scanner = Scanner::with_options(
+            Some(DetectSources::new(dir.clone())),
+            None,
+            ScannerOptions {
+                skip_heuristics: Some(Default::default()),
+                ..Default::default()
+            },
+        );
+
+        let candidates = scanner.scan();
+
+        assert_eq!(candidates, vec!["class", "flex", "img", "src"]);
+        assert_eq!(
+            scanner.get_stats(),
+            ScanStats {
+                files_scanned: 1,
+                bytes_scanned: 67,
+                bytes_skipped: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_report_the_bytes_that_were_read() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                decode_html_entities: true,
+                ..Default::default()
+            },
+        );
+
+        // Decoding shortens the content, but the stats count what was read
+        let content = r#"<p class="[&amp;_p]:mt-2">"#;
+        scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: Some("html"),
+            file: None,
+        }]);
+
+        assert_eq!(scanner.get_stats().bytes_scanned, content.len());
+    }
+
+    #[test]
+    fn it_should_only_return_new_candidates_when_scanning_content() {
+        let mut scanner = Scanner::new(None, None);
+
+        let candidates = scanner.scan_content(vec![
+            ChangedContent::new(None, Some("underline flex".into())),
+            ChangedContent::new(None, Some("flex font-bold".into())),
+        ]);
+        assert_eq!(candidates, vec!["flex", "font-bold", "underline"]);
+
+        let candidates = scanner.scan_content(vec![ChangedContent::new(
+            None,
+            Some("flex md:flex underline".into()),
+        )]);
+        assert_eq!(candidates, vec!["md:flex"]);
+
+        assert_eq!(scanner.get_stats().files_scanned, 3);
+    }
+
+    #[test]
+    fn it_should_scan_in_a_dedicated_thread_pool() {
+        let contents: Vec<_> = (0..50)
+            .map(|n| (format!("file-{}.html", n), format!("p-{} m-{}", n, n % 5)))
+            .collect();
+        let files: Vec<_> = contents
+            .iter()
+            .map(|(path, content)| (path.as_str(), Some(content.as_str())))
+            .collect();
+
+        let (_, expected) = scan(&files);
+
+        for threads in [0, 1, 4] {
+            let dir = tempdir().unwrap().into_path();
+            for (path, content) in &contents {
+                fs::write(dir.join(path), content).unwrap();
+            }
+
+            let mut scanner = Scanner::with_options(
+                Some(DetectSources::new(dir)),
+                None,
+                ScannerOptions {
+                    threads: Some(threads),
+                    ..Default::default()
+                },
+            );
+
+            assert_eq!(scanner.scan(), expected);
+            assert_eq!(scanner.get_stats().files_scanned, 50);
+        }
+    }
+
+    #[test]
+    fn it_should_scan_borrowed_content() {
+        let mut scanner = Scanner::new(None, None);
+
+        let a = b"underline flex".to_vec();
+        let b = b"flex font-bold".to_vec();
+        let candidates = scanner.scan_slices(&[&a, &b]);
+        assert_eq!(candidates, vec!["flex", "font-bold", "underline"]);
+
+        let candidates =
+            scanner.scan_content(vec![ChangedContent::new(None, Some("flex md:flex".into()))]);
+        assert_eq!(candidates, vec!["md:flex"]);
+    }
+
+    #[test]
+    fn it_should_return_candidate_ranges_for_borrowed_content() {
+        let mut scanner = Scanner::new(None, None);
+
+        let content = r#"<div class="flex hover:underline"></div>"#;
+        let ranges: Vec<_> = scanner
+            .get_candidate_ranges(content.as_bytes(), None)
+            .into_iter()
+            .map(|range| &content[range])
+            .collect();
+
+        assert_eq!(ranges, vec!["div", "class", "flex", "hover:underline"]);
+    }
+
+    #[test]
+    fn it_should_filter_candidates_with_the_design_system() {
+        let design_system = |static_utilities: &[&str]| design_system::DesignSystem {
+            utilities: vec!["bg".into()],
+            static_utilities: static_utilities.iter().map(|x| x.to_string()).collect(),
+            variants: vec!["hover".into()],
+            prefix: None,
+        };
+
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                design_system: Some(design_system(&["flex"])),
+                ..Default::default()
+            },
+        );
+
+        let content = b"return the flex hover:bg-red-500 focus:flex underline".to_vec();
+        let candidates = scanner.scan_slices(&[&content]);
+        assert_eq!(candidates, vec!["flex", "hover:bg-red-500"]);
+        assert_eq!(scanner.scan(), vec!["flex", "hover:bg-red-500"]);
+
+        // Updating the design system re-filters all known candidates
+        scanner.set_design_system(Some(design_system(&["flex", "underline"])));
+        assert_eq!(
+            scanner.scan(),
+            vec!["flex", "hover:bg-red-500", "underline"]
+        );
+
+        assert_eq!(
+            scanner.scan_unfiltered(),
+            vec![
+                "flex",
+                "focus:flex",
+                "hover:bg-red-500",
+                "return",
+                "the",
+                "underline"
+            ]
+        );
+
+        scanner.set_design_system(None);
+        assert_eq!(scanner.scan(), scanner.scan_unfiltered());
+    }
+
+    #[test]
+    fn it_should_return_rejected_candidates_once_the_design_system_accepts_them() {
+        let design_system = |static_utilities: &[&str]| design_system::DesignSystem {
+            utilities: vec!["bg".into()],
+            static_utilities: static_utilities.iter().map(|x| x.to_string()).collect(),
+            variants: vec![],
+            prefix: None,
+        };
+
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                design_system: Some(design_system(&["flex"])),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(scanner.scan_slices(&[b"flex underline"]), vec!["flex"]);
+
+        // `underline` was already seen, but never returned
+        scanner.set_design_system(Some(design_system(&["flex", "underline"])));
+        assert_eq!(
+            scanner.scan_slices(&[b"flex bg-red-500"]),
+            vec!["bg-red-500", "underline"]
+        );
+        assert_eq!(
+            scanner.scan_slices(&[b"flex underline"]),
+            Vec::<String>::new()
+        );
+
+        // A full scan returns them first
+        assert_eq!(scanner.scan_slices(&[b"hidden"]), Vec::<String>::new());
+        scanner.set_design_system(Some(design_system(&["flex", "underline", "hidden"])));
+        assert_eq!(
+            scanner.scan(),
+            vec!["bg-red-500", "flex", "hidden", "underline"]
+        );
+        assert_eq!(scanner.scan_slices(&[b"flex"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn it_should_normalize_candidates() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                normalize_candidates: true,
+                ..Default::default()
+            },
+        );
+
+        let candidates = scanner.scan_content(vec![ChangedContent::new(
+            None,
+            Some(r#"!underline underline! -!m-4 !-m-4 hover:!flex content-["hi"]"#.into()),
+        )]);
+
+        // The original spellings are returned, so the generated CSS matches the markup
+        assert_eq!(
+            candidates,
+            vec![
+                "!-m-4",
+                "!underline",
+                "-!m-4",
+                r#"content-["hi"]"#,
+                "hover:!flex",
+                "underline!"
+            ]
+        );
+
+        assert_eq!(
+            scanner.get_spellings(),
+            vec![
+                (
+                    "-m-4!".to_string(),
+                    vec!["!-m-4".to_string(), "-!m-4".to_string()]
+                ),
+                (
+                    "content-['hi']".to_string(),
+                    vec![r#"content-["hi"]"#.to_string()]
+                ),
+                ("hover:flex!".to_string(), vec!["hover:!flex".to_string()]),
+                ("underline!".to_string(), vec!["!underline".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_decode_html_entities_in_html_like_templates() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                decode_html_entities: true,
+                ..Default::default()
+            },
+        );
+
+        let content = r#"<p class="[&amp;_p]:mt-2 content-[&quot;x&quot;]">"#;
+
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: Some("php"),
+            file: None,
+        }]);
+        assert_eq!(
+            candidates,
+            vec!["[&_p]:mt-2", "class", "content-[\"x\"]", "p"]
+        );
+
+        let candidates: Vec<_> = scanner
+            .get_candidates_with_ranges(content.as_bytes(), Some("html"))
+            .into_iter()
+            .map(|(candidate, range)| (candidate, &content[range]))
+            .collect();
+        assert_eq!(
+            candidates,
+            vec![
+                ("p".to_string(), "p"),
+                ("class".to_string(), "class"),
+                ("[&_p]:mt-2".to_string(), "[&amp;_p]:mt-2"),
+                ("content-[\"x\"]".to_string(), "content-[&quot;x&quot;]"),
+            ]
+        );
+
+        // Other extensions are not decoded
+        let candidates = scanner.get_candidate_ranges(content.as_bytes(), Some("js"));
+        assert!(candidates
+            .into_iter()
+            .all(|range| !content[range].contains("[&_p]")));
+    }
+
+    #[test]
+    fn it_should_unescape_string_literals_for_selected_extensions() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                unescape_strings: vec!["json".into()],
+                ..Default::default()
+            },
+        );
+
+        let content = r#"{"html": "<p class='content-[\"hi\"] bg-[url(\"/a.png\")]'>"}"#;
+
+        let candidates: Vec<_> = scanner
+            .get_candidates_with_ranges(content.as_bytes(), Some("json"))
+            .into_iter()
+            .filter(|(candidate, _)| candidate.contains('['))
+            .map(|(candidate, range)| (candidate, &content[range]))
+            .collect();
+        assert_eq!(
+            candidates,
+            vec![
+                (r#"content-["hi"]"#.to_string(), r#"content-[\"hi\"]"#),
+                (
+                    r#"bg-[url("/a.png")]"#.to_string(),
+                    r#"bg-[url(\"/a.png\")]"#
+                ),
+            ]
+        );
+
+        // Other extensions are kept as-is
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: Some("js"),
+            file: None,
+        }]);
+        assert!(!candidates.contains(&r#"content-["hi"]"#.to_string()));
+    }
+
+    #[test]
+    fn it_should_extract_apply_rules_from_stylesheets() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                extract_stylesheets: true,
+                ..Default::default()
+            },
+        );
+
+        let content = ".btn { color: red; @apply px-4 hover:underline !font-bold; }\n.card { @apply flex !important; }";
+        let candidates =
+            scanner.scan_content(vec![
+                ChangedContent::new(None, Some(content.into())).with_extension("css")
+            ]);
+        assert_eq!(
+            candidates,
+            vec!["!font-bold", "flex", "hover:underline", "px-4"]
+        );
+    }
+
+    #[test]
+    fn it_should_extract_apply_rules_from_component_style_blocks() {
+        let mut scanner = Scanner::new(None, None);
+
+        let content = r#"<template><div class="underline"></div></template>
+<style lang="scss">
+.btn {
+  // A button
+  font-weight: bold;
+  @apply md:px-4;
+}
+</style>"#;
+
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: Some("vue"),
+            file: None,
+        }]);
+        assert_eq!(
+            candidates,
+            vec![
+                "class",
+                "div",
+                "lang",
+                "md:px-4",
+                "scss",
+                "style",
+                "template",
+                "underline"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_respect_ignore_and_include_directives() {
+        let dir = tempdir().unwrap().into_path();
+        fs::write(
+            dir.join("index.html"),
+            r#"<div class="flex">
+<!-- tailwind-ignore-start -->
+<p>Lorem ipsum dolor</p>
+<!-- tailwind-ignore-end -->
+<!-- tailwind-include: bg-red-500 bg-blue-500 -->
+<!-- tailwind-ignore-next-line -->
+<p>sit amet</p>
+<!-- tailwind-ignore-start -->
+</div>"#,
+        )
+        .unwrap();
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+
+        let candidates = scanner.scan();
+        assert_eq!(
+            candidates,
+            vec!["bg-blue-500", "bg-red-500", "class", "div", "flex"]
+        );
+
+        let diagnostics = scanner.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].kind,
+            diagnostics::DiagnosticKind::UnmatchedIgnoreStart
+        );
+        assert_eq!(
+            diagnostics[0].file.as_deref(),
+            Some(dir.join("index.html").as_path())
+        );
+    }
+
+    #[test]
+    fn it_should_use_the_file_of_borrowed_content() {
+        let mut scanner = Scanner::new(None, None);
+
+        let content = r#"<template><div class="underline"></div></template>
+<style>
+.btn {
+  @apply md:px-4;
+}
+</style>
+<!-- tailwind-ignore-start -->"#;
+
+        // The extension falls back to the extension of the file
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: None,
+            file: Some(path::Path::new("Button.vue")),
+        }]);
+        assert!(candidates.contains(&"md:px-4".to_string()));
+
+        let diagnostics = scanner.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].file.as_deref(),
+            Some(path::Path::new("Button.vue"))
+        );
+    }
+
+    #[test]
+    fn it_should_strip_comments_for_selected_extensions() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                strip_comments: vec!["jsx".into(), "md".into()],
+                ..Default::default()
+            },
+        );
+
+        let jsx = "let a = <div className=\"flex\" />\n// let b = <div className=\"hidden\" />";
+        let md = "# Usage\n\n```html\n<div class=\"underline\"></div>\n```";
+
+        let candidates = scanner.scan_borrowed(&[
+            BorrowedContent {
+                content: jsx.as_bytes(),
+                extension: Some("jsx"),
+                file: None,
+            },
+            BorrowedContent {
+                content: md.as_bytes(),
+                extension: Some("md"),
+                file: None,
+            },
+        ]);
+        assert_eq!(candidates, vec!["a", "div", "flex", "let"]);
+
+        // Other extensions are kept as-is
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: jsx.as_bytes(),
+            extension: Some("js"),
+            file: None,
+        }]);
+        assert_eq!(candidates, vec!["b", "hidden"]);
+    }
+
+    #[test]
+    fn it_should_only_extract_class_lists_in_strict_mode() {
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                strict_extraction: Some(Default::default()),
+                ..Default::default()
+            },
+        );
+
+        let content = r#"<article class="prose"><p>Lorem ipsum dolor sit amet</p><a :class="{ 'underline': active }">Read more</a></article>"#;
+
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: Some("vue"),
+            file: None,
+        }]);
+        assert_eq!(candidates, vec!["prose", "underline"]);
+
+        // Other extensions are extracted permissively
+        let candidates = scanner.scan_borrowed(&[BorrowedContent {
+            content: content.as_bytes(),
+            extension: Some("md"),
+            file: None,
+        }]);
+        assert!(candidates.contains(&"ipsum".to_string()));
+    }
+
+    #[test]
+    fn it_should_use_custom_extractors() {
+        use scanner::custom_extractors::CustomExtractor;
+
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                custom_extractors: vec![
+                    CustomExtractor {
+                        extensions: vec!["dsl".into()],
+                        patterns: vec![regex::bytes::Regex::new(r"@classes\(([^)]*)\)").unwrap()],
+                        ..Default::default()
+                    },
+                    CustomExtractor {
+                        extensions: vec!["yaml".into()],
+                        patterns: vec![regex::bytes::Regex::new(r"(?m)^\s*classes: (.*)$").unwrap()],
+                        verbatim: true,
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        let dsl = "button @classes(px-4 hover:underline) label Submit the form";
+        let yaml = "button:\n  label: Submit\n  classes: bg-[#0088cc] p-[theme(spacing.4)]";
+
+        let candidates = scanner.scan_borrowed(&[
+            BorrowedContent {
+                content: dsl.as_bytes(),
+                extension: Some("dsl"),
+                file: None,
+            },
+            BorrowedContent {
+                content: yaml.as_bytes(),
+                extension: Some("yaml"),
+                file: None,
+            },
+        ]);
+        assert_eq!(
+            candidates,
+            vec![
+                "bg-[#0088cc]",
+                "hover:underline",
+                "p-[theme(spacing.4)]",
+                "px-4"
+            ]
+        );
+
+        let ranges: Vec<_> = scanner
+            .get_candidates_with_ranges(dsl.as_bytes(), Some("dsl"))
+            .into_iter()
+            .map(|(candidate, range)| (candidate, &dsl[range]))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                ("px-4".to_string(), "px-4"),
+                ("hover:underline".to_string(), "hover:underline")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_transform_content_before_extraction() {
+        use scanner::transformers::TransformInput;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let transformer = {
+            let calls = calls.clone();
+            move |batch: &[TransformInput]| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                batch
+                    .iter()
+                    .map(|input| {
+                        input
+                            .content
+                            .contains("<Button primary")
+                            .then(|| "<button class=\"bg-blue-500 px-4\">".to_string())
+                    })
+                    .collect()
+            }
+        };
+
+        let mut scanner = Scanner::new(None, None);
+        scanner.set_transformer("tpl".into(), Some(Arc::new(transformer)));
+
+        let changed = |file: &str, content: &str, extension: &str| {
+            ChangedContent::new(Some(file.into()), Some(content.into())).with_extension(extension)
+        };
+
+        let candidates = scanner.scan_content(vec![
+            changed("/app/a.tpl", "<Button primary />", "tpl"),
+            changed("/app/b.tpl", "<span class=\"underline\" />", "tpl"),
+            changed("/app/c.html", "<div class=\"flex\">", "html"),
+        ]);
+
+        assert!(candidates.contains(&"bg-blue-500".to_string()));
+        assert!(candidates.contains(&"px-4".to_string()));
+        assert!(!candidates.contains(&"primary".to_string()));
+        assert!(candidates.contains(&"underline".to_string()));
+        assert!(candidates.contains(&"flex".to_string()));
+
+        // All files of an extension are transformed in a single batch
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Unchanged content is cached
+        scanner.scan_content(vec![changed("/app/a.tpl", "<Button primary />", "tpl")]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        scanner.scan_content(vec![changed(
+            "/app/a.tpl",
+            "<Button primary large />",
+            "tpl",
+        )]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Removing the transformer extracts the original content again
+        scanner.set_transformer("tpl".into(), None);
+        let candidates =
+            scanner.scan_content(vec![changed("/app/d.tpl", "<Button primary />", "tpl")]);
+        assert!(candidates.contains(&"primary".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn it_should_map_positions_through_svelte_class_directives() {
+        let dir = tempdir().unwrap().into_path();
+        let content = "<div class:px-4={a} class=\"flex\">\n  <p class:underline class:font-bold={b}>\n</div>";
+        fs::write(dir.join("index.svelte"), content).unwrap();
+
+        let mut scanner = Scanner::new(None, None);
+        let candidates = scanner.get_candidates_with_positions(ChangedContent::new(
+            Some(dir.join("index.svelte")),
+            None,
+        ));
+
+        for candidate in ["px-4", "flex", "underline", "font-bold"] {
+            let (_, position) = candidates
+                .iter()
+                .find(|(x, _)| x == candidate)
+                .unwrap_or_else(|| panic!("{} was not extracted", candidate));
+
+            assert_eq!(&content[*position..*position + candidate.len()], candidate);
+        }
+
+        // In-memory content maps the same way
+        for (candidate, range) in
+            scanner.get_candidates_with_ranges(content.as_bytes(), Some("svelte"))
+        {
+            assert_eq!(&content[range], candidate);
+        }
+    }
+
+    #[test]
+    fn it_should_report_dynamically_constructed_classes() {
+        use diagnostics::DiagnosticKind;
+
+        let dir = tempdir().unwrap().into_path();
+        let content =
+            "<div className={`flex bg-${color}-500`}>\n  <p className={'text-' + size}>\n</div>";
+        fs::write(dir.join("index.jsx"), content).unwrap();
+
+        let mut scanner = Scanner::with_options(
+            None,
+            None,
+            ScannerOptions {
+                detect_dynamic_classes: true,
+                ..Default::default()
+            },
+        );
+        scanner.scan_content(vec![ChangedContent::new(Some(dir.join("index.jsx")), None)]);
+
+        let diagnostics = scanner.get_diagnostics();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| (&content[diagnostic.range.clone()], diagnostic.kind.code()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("bg-", "dynamic-class"),
+                ("-500", "dynamic-class"),
+                ("text-", "dynamic-class")
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::DynamicClass {
+                fragment: "bg-".into()
+            }
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.file == Some(dir.join("index.jsx"))));
+
+        // Disabled by default
+        let mut scanner = Scanner::new(None, None);
+        scanner.scan_slices(&[content.as_bytes()]);
+        assert!(scanner.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn it_should_suggest_fixes_for_typos() {
+        use typos::Typo;
+
+        let content = "<div class=\"flex itmes-center md:bg-gren-500\">Hello world</div>";
+
+        let mut scanner = Scanner::new(None, None);
+        scanner.scan_slices(&[content.as_bytes()]);
+
+        // Nothing is reported without valid classes
+        assert_eq!(scanner.get_typos(), vec![]);
+
+        scanner.set_valid_classes(Some(
+            ["flex", "items-center", "bg-green-500"]
+                .map(Into::into)
+                .to_vec(),
+        ));
+
+        assert_eq!(
+            scanner.get_typos(),
+            vec![
+                Typo {
+                    candidate: "itmes-center".into(),
+                    suggestions: vec!["items-center".into()],
+                },
+                Typo {
+                    candidate: "md:bg-gren-500".into(),
+                    suggestions: vec!["md:bg-green-500".into()],
+                },
+            ]
+        );
+
+        let typos: Vec<_> = scanner
+            .get_typos_with_ranges(content.as_bytes(), Some("html"))
+            .into_iter()
+            .map(|(typo, range)| (typo.suggestions, &content[range]))
+            .collect();
+        assert_eq!(
+            typos,
+            vec![
+                (vec!["items-center".to_string()], "itmes-center"),
+                (vec!["md:bg-green-500".to_string()], "md:bg-gren-500"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_find_class_list_spans() {
+        let content = "<div class=\"flex p-4\">\n<!-- tailwind-ignore-next-line -->\n<p class=\"underline\"></p>\n<button className={cn('px-4', active && \"font-bold\")} />\n</div>";
+
+        let scanner = Scanner::new(None, None);
+        let spans: Vec<_> = scanner
+            .get_class_list_spans(content.as_bytes(), Some("html"))
+            .into_iter()
+            .map(|span| (&content[span.range], span.context.kind()))
+            .collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("flex p-4", "attribute"),
+                ("px-4", "expression"),
+                ("font-bold", "expression")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_rewrite_candidates() {
+        let content = "<div class=\"flex shadow\">\n  <!-- tailwind-ignore-next-line -->\n  <p class=\"shadow\">é</p>\n  <p class=\"hover:shadow bg-red-500/50\"></p>\n</div>";
+
+        let mut scanner = Scanner::new(None, None);
+        let rewritten = scanner.rewrite(content, Some("html"), |candidate| match candidate {
+            "shadow" => Some("shadow-sm".into()),
+            "hover:shadow" => Some("hover:shadow-sm".into()),
+            "bg-red-500/50" => Some("bg-red-500/[0.5]".into()),
+            _ => None,
+        });
+
+        assert_eq!(
+            rewritten.content,
+            "<div class=\"flex shadow-sm\">\n  <!-- tailwind-ignore-next-line -->\n  <p class=\"shadow\">é</p>\n  <p class=\"hover:shadow-sm bg-red-500/[0.5]\"></p>\n</div>"
+        );
+
+        let utf16: Vec<u16> = content.encode_utf16().collect();
+        for edit in &rewritten.edits {
+            assert_eq!(&content[edit.range.clone()], edit.original);
+            assert_eq!(
+                String::from_utf16(&utf16[edit.utf16_range.clone()]).unwrap(),
+                edit.original
+            );
+        }
+        assert_eq!(rewritten.edits.len(), 3);
+    }
+
+    #[test]
+    fn it_should_run_codemods_over_all_files() {
+        let dir = tempdir().unwrap().into_path();
+        let _ = Command::new("git").arg("init").current_dir(&dir).output();
+
+        let html = "<div class=\"shadow !flex\">\n  <p>Hello world</p>\n</div>\n";
+        let jsx = "export let a = <div className=\"flex\" />\n";
+        fs::write(dir.join("index.html"), html).unwrap();
+        fs::write(dir.join("a.jsx"), jsx).unwrap();
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+
+        // Dry-run mode only returns diffs
+        let codemod = scanner.codemod(&renames(), true);
+        assert!(codemod.errors.is_empty());
+        assert_eq!(codemod.changes.len(), 1);
+        assert_eq!(codemod.changes[0].file, dir.join("index.html"));
+        assert_eq!(
+            codemod.changes[0]
+                .diff
+                .as_deref()
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "--- a/index.html",
+                "+++ b/index.html",
+                "@@ -1,3 +1,3 @@",
+                "-<div class=\"shadow !flex\">",
+                "+<div class=\"shadow-sm flex!\">",
+                "   <p>Hello world</p>",
+                " </div>",
+            ]
+        );
+        assert_eq!(fs::read_to_string(dir.join("index.html")).unwrap(), html);
+
+        let codemod = scanner.codemod(&renames(), false);
+        assert!(codemod.changes[0].diff.is_none());
+        assert_eq!(
+            fs::read_to_string(dir.join("index.html")).unwrap(),
+            "<div class=\"shadow-sm flex!\">\n  <p>Hello world</p>\n</div>\n"
+        );
+        assert_eq!(fs::read_to_string(dir.join("a.jsx")).unwrap(), jsx);
+    }
+
+    #[test]
+    fn it_should_only_run_codemods_on_class_lists() {
+        let dir = tempdir().unwrap().into_path();
+        let _ = Command::new("git").arg("init").current_dir(&dir).output();
+
+        fs::write(
+            dir.join("card.jsx"),
+            "const shadow = { shadow: 1 }\nexport let Card = () => (\n  <div className={clsx(\"shadow\", shadow && \"p-4\")}>Cast a shadow</div>\n)\n",
+        )
+        .unwrap();
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+        let codemod = scanner.codemod(&renames(), false);
+
+        assert_eq!(codemod.changes.len(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.join("card.jsx")).unwrap(),
+            "const shadow = { shadow: 1 }\nexport let Card = () => (\n  <div className={clsx(\"shadow-sm\", shadow && \"p-4\")}>Cast a shadow</div>\n)\n"
+        );
+    }
+
+    fn renames() -> codemod::RenameTable {
+        codemod::RenameTable {
+            exact: [("shadow".to_string(), "shadow-sm".to_string())]
+                .into_iter()
+                .collect(),
+            patterns: vec![(regex::Regex::new(r"^!(.*)$").unwrap(), "$1!".into())],
+        }
+    }
+
+    #[test]
+    fn it_should_normalize_class_lists() {
+        let content = "<div class=\"p-4 flex p-4\" title=\"é\">\n  <!-- tailwind-ignore-next-line -->\n  <p class=\"p-4 flex\"></p>\n  <p className={`p-4 flex bg-${color}`}></p>\n</div>";
+
+        let mut scanner = Scanner::new(None, None);
+        scanner.set_class_order(Some(vec![("flex".into(), 0), ("p-4".into(), 1)]));
+
+        let rewritten = scanner.normalize_class_lists(content, Some("html"));
+        assert_eq!(
+            rewritten.content,
+            "<div class=\"flex p-4\" title=\"é\">\n  <!-- tailwind-ignore-next-line -->\n  <p class=\"p-4 flex\"></p>\n  <p className={`flex p-4 bg-${color}`}></p>\n</div>"
+        );
+
+        let utf16: Vec<u16> = content.encode_utf16().collect();
+        for edit in &rewritten.edits {
+            assert_eq!(
+                String::from_utf16(&utf16[edit.utf16_range.clone()]).unwrap(),
+                edit.original
+            );
+        }
+        assert_eq!(rewritten.edits.len(), 2);
+    }
 }